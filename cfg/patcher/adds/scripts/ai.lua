@@ -0,0 +1,26 @@
+---- Computer players of practice maps, BTD_AIPlayers table is generated by patcher.
+---- Aggressiveness sets what towns AI heroes are attracted to, bonus is given on day one like handicap.
+
+BTD_AI_DEFENSIVE = 0
+BTD_AI_AGGRESSIVE = 2
+
+function BTD_SetupAIPlayer(player, info)
+    if info.aggressiveness == BTD_AI_DEFENSIVE or info.aggressiveness == BTD_AI_AGGRESSIVE then
+        for town, town_info in BTD_Towns do
+            local is_own = GetObjectOwner(town) == player
+            -- defensive AI keeps its heroes near own towns, aggressive one goes for towns of others
+            if is_own == (info.aggressiveness == BTD_AI_DEFENSIVE) then
+                SetAIPlayerAttractor(town, player, 2)
+            end
+        end
+    end
+    if info.bonus ~= nil and GetDate(DAY) == 1 then
+        BTD_ApplyHandicap(player, info.bonus)
+    end
+end
+
+for player, info in BTD_AIPlayers do
+    if GetPlayerState(player) == PLAYER_ACTIVE then
+        BTD_SetupAIPlayer(player, info)
+    end
+end
@@ -0,0 +1,26 @@
+---- Artifacts placed on map, BTD_Artifacts table(object name -> id and position) is generated by patcher.
+---- Picked up artifacts are removed from table every day, so it always describes artifacts still lying on map.
+
+function BTD_GetMapArtifacts(id)
+    local names = {}
+    for name, info in BTD_Artifacts do
+        if info.id == id then
+            names[length(names) + 1] = name
+        end
+    end
+    return names
+end
+
+function BTD_UpdateMapArtifacts()
+    local picked = {}
+    for name, info in BTD_Artifacts do
+        if not IsObjectExists(name) then
+            picked[length(picked) + 1] = name
+        end
+    end
+    for i, name in picked do
+        BTD_Artifacts[name] = nil
+    end
+end
+
+BTD_AddNewDayHandler(BTD_UpdateMapArtifacts)
@@ -0,0 +1,27 @@
+---- Spells and skills bans of map, tables are generated by patcher.
+---- Map's game files keep banned spells out of guilds and banned skills out of level ups,
+---- other modules give spells and skills only through functions below, so they can't give banned ones.
+
+function BTD_IsSpellBanned(spell)
+    return BTD_BannedSpells ~= nil and BTD_BannedSpells[spell] == true
+end
+
+function BTD_IsSkillBanned(skill)
+    return BTD_BannedSkills ~= nil and BTD_BannedSkills[skill] == true
+end
+
+function BTD_TeachHeroSpell(hero, spell)
+    if BTD_IsSpellBanned(spell) then
+        print("BTD: spell ", spell, " is banned and isn't taught to ", hero)
+        return
+    end
+    TeachHeroSpell(hero, spell)
+end
+
+function BTD_GiveHeroSkill(hero, skill)
+    if BTD_IsSkillBanned(skill) then
+        print("BTD: skill ", skill, " is banned and isn't given to ", hero)
+        return
+    end
+    GiveHeroSkill(hero, skill)
+end
@@ -0,0 +1,25 @@
+---- Capital mode: player loses when the first town they owned at the start is captured.
+---- Objectives LOSE_CONDITION_CAPITAL_<player> are generated by patcher for every active player.
+
+BTD_Capitals = {}
+
+function BTD_DetectCapitals()
+    for town, info in BTD_Towns do
+        local owner = GetObjectOwner(town)
+        if owner ~= PLAYER_NONE and BTD_Capitals[owner] == nil then
+            BTD_Capitals[owner] = town
+        end
+    end
+end
+
+function BTD_CheckCapitals()
+    for player, town in BTD_Capitals do
+        if GetPlayerState(player) == PLAYER_ACTIVE and GetObjectOwner(town) ~= player then
+            SetObjectiveState("LOSE_CONDITION_CAPITAL_"..player, OBJECTIVE_FAILED, player)
+            Loose(player)
+        end
+    end
+end
+
+BTD_DetectCapitals()
+BTD_AddNewDayHandler(BTD_CheckCapitals)
@@ -0,0 +1,32 @@
+---- Core module of map script, it is included into every patched map.
+---- BTD_PatchInfo is read here, MCCS_GAME_MODES, BTD_Towns, BTD_Stacks, BTD_Treasures, BTD_BanksInfo, BTD_NewObjects
+---- and BTD_DwarvenMinesRots are read by game scripts of the mod, other generated tables are read by modules declaring them.
+
+function BTD_PrintPatchInfo()
+    if BTD_PatchInfo == nil then
+        print("BTD: map isn't patched or btd_patch_info.lua is missing")
+        return
+    end
+    print("BTD: map ", BTD_PatchInfo.map, " patched by launcher ", BTD_PatchInfo.launcher_version, ", patcher ", BTD_PatchInfo.patcher_version)
+    print("BTD: seed ", BTD_PatchInfo.seed)
+    for i, mode in BTD_PatchInfo.modes do
+        print("BTD: mode ", mode, " is active")
+    end
+end
+
+BTD_PrintPatchInfo()
+
+-- game keeps only one function per trigger, so modules add their new day handlers here instead of setting the trigger
+BTD_NewDayHandlers = {}
+
+function BTD_AddNewDayHandler(handler)
+    BTD_NewDayHandlers[length(BTD_NewDayHandlers) + 1] = handler
+end
+
+function BTD_OnNewDay()
+    for i, handler in BTD_NewDayHandlers do
+        handler()
+    end
+end
+
+Trigger(NEW_DAY_TRIGGER, "BTD_OnNewDay")
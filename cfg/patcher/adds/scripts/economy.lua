@@ -0,0 +1,41 @@
+---- Production of mines and dwellings changed by economy rules, BTD_Mines and BTD_Dwellings tables are generated by patcher.
+---- Owners of mines get more(or less) resources every day, creatures available in dwellings are multiplied every week.
+
+BTD_MineDailyIncome = {
+    [WOOD] = 2, [ORE] = 2, [MERCURY] = 1, [CRYSTAL] = 1, [SULFUR] = 1, [GEM] = 1, [GOLD] = 1000
+}
+
+function BTD_ApplyMinesProduction()
+    if BTD_Mines == nil then
+        return
+    end
+    for mine, info in BTD_Mines do
+        local owner = GetObjectOwner(mine)
+        if info.production ~= 1 and owner ~= PLAYER_NONE then
+            local amount = GetPlayerResource(owner, info.resource) + math.floor(BTD_MineDailyIncome[info.resource] * (info.production - 1))
+            if amount < 0 then
+                amount = 0
+            end
+            SetPlayerResource(owner, info.resource, amount)
+        end
+    end
+end
+
+function BTD_ApplyDwellingsProduction()
+    if BTD_Dwellings == nil or GetDate(DAY_OF_WEEK) ~= 1 then
+        return
+    end
+    for dwelling, info in BTD_Dwellings do
+        if info.production ~= 1 and info.creature ~= nil then
+            local count = GetObjectDwellingCreatures(dwelling, info.creature)
+            SetObjectDwellingCreatures(dwelling, info.creature, math.floor(count * info.production))
+        end
+    end
+end
+
+function BTD_ApplyProduction()
+    BTD_ApplyMinesProduction()
+    BTD_ApplyDwellingsProduction()
+end
+
+BTD_AddNewDayHandler(BTD_ApplyProduction)
@@ -0,0 +1,38 @@
+---- Handicaps of players, BTD_Handicaps and BTD_StartingHeroes tables are generated by patcher.
+---- Bonuses are given on day one: resources to player, creatures and experience to player's starting hero
+---- (hero assigned by organisers or the first hero of player if there is no such one).
+
+function BTD_GetStartingHero(player)
+    if BTD_StartingHeroes ~= nil and BTD_StartingHeroes[player] ~= nil and IsHeroAlive(BTD_StartingHeroes[player]) then
+        return BTD_StartingHeroes[player]
+    end
+    for i, hero in GetPlayerHeroes(player) do
+        return hero
+    end
+    return nil
+end
+
+function BTD_ApplyHandicap(player, handicap)
+    for resource, amount in handicap.resources do
+        SetPlayerResource(player, resource, GetPlayerResource(player, resource) + amount)
+    end
+    local hero = BTD_GetStartingHero(player)
+    if hero == nil then
+        print("BTD: player ", player, " has no hero to get handicap creatures and experience")
+        return
+    end
+    for i, stack in handicap.creatures do
+        AddHeroCreatures(hero, stack.id, stack.count)
+    end
+    if handicap.experience > 0 then
+        ChangeHeroStat(hero, STAT_EXPERIENCE, handicap.experience)
+    end
+end
+
+if BTD_Handicaps ~= nil and GetDate(DAY) == 1 then
+    for player, handicap in BTD_Handicaps do
+        if GetPlayerState(player) == PLAYER_ACTIVE then
+            BTD_ApplyHandicap(player, handicap)
+        end
+    end
+end
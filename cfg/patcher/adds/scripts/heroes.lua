@@ -0,0 +1,44 @@
+---- Adjustments of pre-placed heroes, BTD_Heroes table is generated by patcher.
+---- Heroes without army, skills, spells and experience fields are left as they are, banned ones aren't given(see bans module).
+
+function BTD_ReplaceHeroArmy(hero, army)
+    -- counts of old stacks are remembered first, cause new creatures can be of the same types
+    local old_army = {}
+    for i, creature in {GetHeroCreaturesTypes(hero)} do
+        if creature ~= nil and creature ~= 0 then
+            old_army[creature] = GetHeroCreatures(hero, creature)
+        end
+    end
+    -- new creatures are added before old ones are removed, hero can't stay without army
+    for i, stack in army do
+        AddHeroCreatures(hero, stack.id, stack.count)
+    end
+    for creature, count in old_army do
+        RemoveHeroCreatures(hero, creature, count)
+    end
+end
+
+function BTD_AdjustHero(hero, info)
+    if info.army ~= nil and info.army[1] ~= nil then
+        BTD_ReplaceHeroArmy(hero, info.army)
+    end
+    if info.skills ~= nil then
+        for i, skill in info.skills do
+            BTD_GiveHeroSkill(hero, skill)
+        end
+    end
+    if info.spells ~= nil then
+        for i, spell in info.spells do
+            BTD_TeachHeroSpell(hero, spell)
+        end
+    end
+    if info.experience ~= nil and info.experience > 0 then
+        ChangeHeroStat(hero, STAT_EXPERIENCE, info.experience)
+    end
+end
+
+for hero, info in BTD_Heroes do
+    if IsHeroAlive(hero) then
+        BTD_AdjustHero(hero, info)
+    end
+end
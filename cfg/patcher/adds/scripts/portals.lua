@@ -0,0 +1,27 @@
+---- Two-way portals, BTD_Portals table(portal name -> group) is generated by patcher, every group has exactly two portals.
+---- When hero enters portal, surroundings of its exit are revealed to hero's owner.
+
+BTD_PortalExitRevealRadius = 6
+
+function BTD_GetPortalExit(portal)
+    local group = BTD_Portals[portal]
+    for other, other_group in BTD_Portals do
+        if other ~= portal and other_group == group then
+            return other
+        end
+    end
+    return nil
+end
+
+function BTD_OnPortalTouch(hero, portal)
+    local exit = BTD_GetPortalExit(portal)
+    if exit == nil then
+        return
+    end
+    local x, y, floor = GetObjectPosition(exit)
+    OpenCircleFog(x, y, floor, BTD_PortalExitRevealRadius, GetObjectOwner(hero))
+end
+
+for portal, group in BTD_Portals do
+    Trigger(OBJECT_TOUCH_TRIGGER, portal, "BTD_OnPortalTouch")
+end
@@ -1,7 +1,12 @@
 pub mod patch_strategy;
 pub mod map;
+pub mod report;
+pub mod lua;
+pub mod text;
+pub mod locale;
 
 use patch_strategy::{GenerateLuaCode, PatchCreatable, WriteAdditional, ProcessText, PatchGroup};
+use text::Utf16Text;
 
 use std::collections::HashMap;
 use std::io::{Read, Write};
@@ -13,9 +18,6 @@ use quick_xml::Writer;
 use quick_xml::events::{BytesStart, Event, BytesEnd, BytesDecl};
 use quick_xml::reader::Reader;
 
-use pyo3::prelude::*;
-use pyo3::types::{PyAny, PyModule, IntoPyDict};
-
 /// Patcher performs all possible strategies for given file.
 
 pub struct Patcher<'a> {
@@ -86,7 +88,11 @@ impl<'a> Patcher<'a> {
 
     /// reads all tags in given xml file.
     /// if finds tag that is creatable/modifiable then applies necessary patches to its content
+    /// if modifiable object was removed by its group, its parent tag(Item of objects list) is removed too.
     fn process(&mut self, writer: &mut Writer<&mut Vec<u8>>) {
+        // positions in output where currently opened tags start
+        let mut starts: Vec<usize> = vec![];
+        let mut skip_parent_end = false;
         let mut buf = Vec::new();
         let mut reader = Reader::from_str(&self.readable);
         reader.trim_text(true);
@@ -102,6 +108,10 @@ impl<'a> Patcher<'a> {
                         if *self.creatables.get(&actual_tag).unwrap() == true {
                             reader.read_to_end(e.to_end().name()).unwrap();
                         }
+                        else {
+                            // content of not replaceable tag is still read, so its end will pop this start
+                            starts.push(writer.get_ref().len());
+                        }
                         //println!("creatable tag found: {}", &actual_tag);
                         let actual_strategy = self.creatable_patches.get_mut(&actual_tag).unwrap();
                         actual_strategy.try_create(writer);
@@ -112,19 +122,34 @@ impl<'a> Patcher<'a> {
                         let text = reader.read_text(end.name()).unwrap().to_string();
                         let strategy = self.modifyable_patches.get_mut(&actual_tag).unwrap();
                         strategy.run(&text, writer);
+                        if strategy.take_removal() == true {
+                            if let Some(parent_start) = starts.last() {
+                                writer.get_mut().truncate(*parent_start);
+                                skip_parent_end = true;
+                            }
+                        }
                     }
                     else {
+                        starts.push(writer.get_ref().len());
                         let mut elem = BytesStart::new(str::from_utf8(e.name().0).unwrap());
                         elem.extend_attributes(e.attributes().map(|attr| attr.unwrap()));
                         writer.write_event(Event::Start(elem)).unwrap();
                     }
                 }
                 Ok(Event::Text(e)) => {
-                    writer.write_event(Event::Text(e)).unwrap();
+                    if skip_parent_end == false {
+                        writer.write_event(Event::Text(e)).unwrap();
+                    }
                 },
                 Ok(Event::End(e)) => {
-                    let elem = BytesEnd::new(str::from_utf8(e.name().0).unwrap());
-                    writer.write_event(Event::End(elem)).unwrap();
+                    starts.pop();
+                    if skip_parent_end == true {
+                        skip_parent_end = false;
+                    }
+                    else {
+                        let elem = BytesEnd::new(str::from_utf8(e.name().0).unwrap());
+                        writer.write_event(Event::End(elem)).unwrap();
+                    }
                 },
                 _ => ()
             }
@@ -134,7 +159,7 @@ impl<'a> Patcher<'a> {
 }
 
 /// Modifies text file of map.
-/// This is needed cause of stupid encoding of these files so i'm using this python solution here(couldnt find right way to output utf16-le in Rust)
+/// Text files of game are UTF-16LE, so they are read and written with text::Utf16Text.
 pub struct TextProcessor<'a> {
     path: Option<&'a PathBuf>,
     processors: Vec<&'a dyn ProcessText>
@@ -156,27 +181,19 @@ impl<'a> TextProcessor<'a> {
 
     /// apply all processors to text
     pub fn run(&self) {
-        let file = fs::File::open(self.path.unwrap()).unwrap();
-        let mut text = utf16_reader::read_to_string(file);
-        fs::remove_file(self.path.unwrap()).unwrap();
+        let mut text = match Utf16Text::read(self.path.unwrap()) {
+            Ok(text) => text,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
         for processor in &self.processors {
-            text = processor.try_process(&mut text);
+            text.text = processor.try_process(&mut text.text);
+        }
+        if let Err(e) = text.write(self.path.unwrap()) {
+            println!("{}", e);
         }
-        let code = 
-        "def save_utf16file(*args, **kwargs):
-            with open(kwargs['path'], encoding='utf16', mode='w') as out:
-                out.write(kwargs['text'])";
-        fs::File::create(self.path.unwrap()).unwrap();
-        pyo3::prepare_freethreaded_python();
-        Python::with_gil(|py| {
-            let fun: Py<PyAny> = PyModule::from_code(py, &code, "", "")
-                        .unwrap().getattr("save_utf16file").unwrap().into();
-            let kwargs = vec![
-                ("path", self.path.unwrap().to_str().unwrap()), 
-                ("text", &text)
-            ];
-            fun.call(py, (), Some(kwargs.into_py_dict(py))).unwrap();
-        });
     }
 }
 
@@ -202,6 +219,13 @@ impl<'a> CodeGenerator<'a> {
             generator.to_lua(base_path);
         }
     }
+
+    /// Names of not empty tables all generators produce.
+    pub fn generated_tables(&self) -> Vec<&'static str> {
+        self.code_generators.iter()
+            .flat_map(|g| g.generated_tables())
+            .collect()
+    }
 }
 
 /// Puts additional files to map(from configs mostly)
@@ -0,0 +1,135 @@
+/// Localization of launcher and patcher output.
+/// Every locale has its own string table in cfg/locale/(ru.json, en.json). Tables are json objects,
+/// nested objects give dotted keys("resources.gold" for example). Strings can contain {name} placeholders.
+/// Missing strings are taken from default locale, so incomplete table doesn't break anything.
+
+use std::{path::PathBuf, collections::HashMap};
+use serde::{Serialize, Deserialize};
+use strum_macros::EnumString;
+
+#[derive(Default, Deserialize, Serialize, PartialEq, Eq, Hash, Debug, Clone, Copy, EnumString)]
+pub enum Locale {
+    #[default]
+    Ru,
+    En
+}
+
+impl Locale {
+    pub fn all() -> [Locale; 2] {
+        [Locale::Ru, Locale::En]
+    }
+
+    /// Name of locale's table file and locale's directories.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::Ru => "ru",
+            Locale::En => "en"
+        }
+    }
+}
+
+fn flatten(prefix: &str, value: &serde_json::Value, table: &mut HashMap<String, String>) {
+    match value {
+        serde_json::Value::Object(object) => {
+            for (key, value) in object.iter() {
+                let key = match prefix.is_empty() {
+                    true => key.clone(),
+                    false => format!("{}.{}", prefix, key)
+                };
+                flatten(&key, value, table);
+            }
+        },
+        serde_json::Value::String(s) => {
+            table.insert(prefix.to_string(), s.clone());
+        },
+        _=> {
+            table.insert(prefix.to_string(), value.to_string());
+        }
+    }
+}
+
+pub struct LocalizationService {
+    locale: Locale,
+    tables: HashMap<Locale, HashMap<String, String>>
+}
+
+impl LocalizationService {
+    pub fn new(locale_dir: &PathBuf, locale: Locale) -> Self {
+        let mut tables = HashMap::new();
+        for table_locale in Locale::all() {
+            let table_de: Result<serde_json::Value, String> = std::fs::read_to_string(locale_dir.join(format!("{}.json", table_locale.code())))
+                .map_err(|e| e.to_string())
+                .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()));
+            let mut table = HashMap::new();
+            match table_de {
+                Ok(value) => flatten("", &value, &mut table),
+                Err(e) => println!("Error reading strings of locale {:?}: {}", table_locale, e)
+            }
+            tables.insert(table_locale, table);
+        }
+        LocalizationService {
+            locale: locale,
+            tables: tables
+        }
+    }
+
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    pub fn set_locale(&mut self, locale: Locale) {
+        self.locale = locale;
+    }
+
+    /// Returns string of active locale, string of default locale if it is missing, or key itself if there is no such string at all.
+    /// Missing strings are reported by missing_keys.
+    pub fn get(&self, key: &str) -> String {
+        self.tables.get(&self.locale).and_then(|t| t.get(key))
+            .or_else(|| self.tables.get(&Locale::default()).and_then(|t| t.get(key)))
+            .cloned()
+            .unwrap_or_else(|| key.to_string())
+    }
+
+    /// Returns string with {name} placeholders replaced with given values.
+    pub fn format(&self, key: &str, values: &[(&str, &str)]) -> String {
+        let mut string = self.get(key);
+        for (name, value) in values.iter() {
+            string = string.replace(&format!("{{{}}}", name), value);
+        }
+        string
+    }
+
+    /// Returns all strings of active locale, missing ones are taken from default locale.
+    pub fn strings(&self) -> HashMap<String, String> {
+        let mut strings = self.tables.get(&Locale::default()).cloned().unwrap_or_default();
+        if let Some(table) = self.tables.get(&self.locale) {
+            strings.extend(table.clone());
+        }
+        strings
+    }
+
+    /// Returns keys every locale lacks, key is missing if any other locale has it.
+    pub fn missing_keys(&self) -> HashMap<Locale, Vec<String>> {
+        let mut all_keys: Vec<&String> = self.tables.values().flat_map(|t| t.keys()).collect();
+        all_keys.sort();
+        all_keys.dedup();
+        self.tables.iter()
+            .map(|(locale, table)| {
+                let missing = all_keys.iter()
+                    .filter(|k| table.contains_key(**k) == false)
+                    .map(|k| (*k).clone())
+                    .collect();
+                (*locale, missing)
+            })
+            .collect()
+    }
+
+    /// Returns version of file for active locale(dir/<locale code>/file) if it exists, otherwise dir/file.
+    pub fn localized_file(&self, dir: &PathBuf, file: &str) -> PathBuf {
+        let localized = dir.join(self.locale.code()).join(file);
+        match localized.exists() {
+            true => localized,
+            false => dir.join(file)
+        }
+    }
+}
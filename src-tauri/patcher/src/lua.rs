@@ -0,0 +1,538 @@
+//! Small model of lua values used to generate lua files of patched map.
+//! All generated tables must be built with it, so strings are always escaped and files have the same layout.
+
+use std::{path::PathBuf, io::Write};
+
+const LUA_KEYWORDS: [&'static str; 21] = [
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if",
+    "in", "local", "nil", "not", "or", "repeat", "return", "then", "true", "until"
+];
+
+/// Checks if string can be used as lua name(of constant or table field).
+fn is_lua_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {},
+        _=> return false
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_') && LUA_KEYWORDS.contains(&s) == false
+}
+
+/// Returns quoted lua string literal with all special characters escaped.
+pub fn escape_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            // other control characters are written with their decimal codes, 3 digits to not mix with following digits
+            c if (c as u32) < 0x20 || c as u32 == 0x7f => escaped += &format!("\\{:03}", c as u32),
+            c => escaped.push(c)
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LuaKey {
+    /// name = value
+    Name(String),
+    /// ["string"] = value
+    String(String),
+    /// [CONSTANT] = value
+    Constant(String),
+    /// [1] = value
+    Integer(i64)
+}
+
+impl LuaKey {
+    fn to_lua(&self) -> String {
+        match self {
+            LuaKey::Name(name) if is_lua_name(name) => name.clone(),
+            // names that aren't correct lua names can be written as strings only
+            LuaKey::Name(name) | LuaKey::String(name) => format!("[{}]", escape_string(name)),
+            LuaKey::Constant(constant) => format!("[{}]", LuaValue::Constant(constant.clone()).to_lua(0)),
+            LuaKey::Integer(i) => format!("[{}]", i)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LuaValue {
+    Nil,
+    Bool(bool),
+    Integer(i64),
+    Number(f64),
+    String(String),
+    /// Global name written as is, game constants mostly(TREASURE_GOLD, PLAYER_1...).
+    Constant(String),
+    Table(LuaTable)
+}
+
+impl LuaValue {
+    /// Lua presentation of value, depth is a count of tabs nested tables entries are indented with.
+    pub fn to_lua(&self, depth: usize) -> String {
+        match self {
+            LuaValue::Nil => "nil".to_string(),
+            LuaValue::Bool(b) => b.to_string(),
+            LuaValue::Integer(i) => i.to_string(),
+            LuaValue::Number(n) if n.is_finite() == false => {
+                println!("Impossible to write number {} into lua, nil is written", n);
+                "nil".to_string()
+            },
+            // numbers always have a point to not be read as integers
+            LuaValue::Number(n) if n.fract() == 0.0 => format!("{:.1}", n),
+            LuaValue::Number(n) => n.to_string(),
+            LuaValue::String(s) => escape_string(s),
+            LuaValue::Constant(constant) if is_lua_name(constant) => constant.clone(),
+            LuaValue::Constant(constant) => {
+                println!("{} is not a correct lua name, it is written as string", constant);
+                escape_string(constant)
+            },
+            LuaValue::Table(table) => table.to_lua(depth)
+        }
+    }
+}
+
+impl From<bool> for LuaValue {
+    fn from(value: bool) -> Self {
+        LuaValue::Bool(value)
+    }
+}
+
+impl From<&str> for LuaValue {
+    fn from(value: &str) -> Self {
+        LuaValue::String(value.to_string())
+    }
+}
+
+impl From<String> for LuaValue {
+    fn from(value: String) -> Self {
+        LuaValue::String(value)
+    }
+}
+
+impl From<&String> for LuaValue {
+    fn from(value: &String) -> Self {
+        LuaValue::String(value.clone())
+    }
+}
+
+impl From<f32> for LuaValue {
+    fn from(value: f32) -> Self {
+        LuaValue::Number(value as f64)
+    }
+}
+
+impl From<f64> for LuaValue {
+    fn from(value: f64) -> Self {
+        LuaValue::Number(value)
+    }
+}
+
+impl From<LuaTable> for LuaValue {
+    fn from(value: LuaTable) -> Self {
+        LuaValue::Table(value)
+    }
+}
+
+/// Json values keep their types, arrays and objects become tables.
+impl From<&serde_json::Value> for LuaValue {
+    fn from(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => LuaValue::Nil,
+            serde_json::Value::Bool(b) => LuaValue::Bool(*b),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => LuaValue::Integer(i),
+                None => LuaValue::Number(n.as_f64().unwrap_or(f64::NAN))
+            },
+            serde_json::Value::String(s) => LuaValue::String(s.clone()),
+            serde_json::Value::Array(values) => {
+                let mut table = LuaTable::new();
+                for value in values.iter() {
+                    table.push(value);
+                }
+                LuaValue::Table(table)
+            },
+            serde_json::Value::Object(fields) => {
+                let mut table = LuaTable::new();
+                for field in fields.iter() {
+                    table.insert(LuaKey::Name(field.0.clone()), field.1);
+                }
+                LuaValue::Table(table)
+            }
+        }
+    }
+}
+
+macro_rules! impl_from_integer {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for LuaValue {
+                fn from(value: $t) -> Self {
+                    LuaValue::Integer(value as i64)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_integer!(i8, i16, i32, i64, u8, u16, u32, usize);
+
+/// Lua table, entries keep order they were added in. Entries without keys are list items.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LuaTable {
+    entries: Vec<(Option<LuaKey>, LuaValue)>
+}
+
+impl LuaTable {
+    pub fn new() -> Self {
+        LuaTable {
+            entries: vec![]
+        }
+    }
+
+    pub fn with(mut self, key: LuaKey, value: impl Into<LuaValue>) -> Self {
+        self.insert(key, value);
+        self
+    }
+
+    /// Adds name = value entry.
+    pub fn with_field(self, name: &str, value: impl Into<LuaValue>) -> Self {
+        self.with(LuaKey::Name(name.to_string()), value)
+    }
+
+    pub fn insert(&mut self, key: LuaKey, value: impl Into<LuaValue>) {
+        self.entries.push((Some(key), value.into()));
+    }
+
+    /// Adds list item.
+    pub fn push(&mut self, value: impl Into<LuaValue>) {
+        self.entries.push((None, value.into()));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> &Vec<(Option<LuaKey>, LuaValue)> {
+        &self.entries
+    }
+
+    /// Tables without nested tables are written in one line, others are written entry per line.
+    fn to_lua(&self, depth: usize) -> String {
+        if self.entries.is_empty() {
+            return "{}".to_string();
+        }
+        let is_flat = self.entries.iter().all(|e| matches!(e.1, LuaValue::Table(_)) == false);
+        let entries = self.entries.iter()
+            .map(|e| {
+                match &e.0 {
+                    Some(key) => format!("{} = {}", key.to_lua(), e.1.to_lua(depth + 1)),
+                    None => e.1.to_lua(depth + 1)
+                }
+            });
+        if is_flat {
+            format!("{{{}}}", entries.collect::<Vec<String>>().join(", "))
+        }
+        else {
+            let indent = "\t".repeat(depth + 1);
+            let mut output = "{\n".to_string();
+            for entry in entries {
+                output += &format!("{}{},\n", &indent, entry);
+            }
+            output += &format!("{}}}", "\t".repeat(depth));
+            output
+        }
+    }
+}
+
+/// Lua file that contains global variables assignments.
+pub struct LuaChunk {
+    globals: Vec<(String, LuaValue)>
+}
+
+impl LuaChunk {
+    pub fn new() -> Self {
+        LuaChunk {
+            globals: vec![]
+        }
+    }
+
+    pub fn with(mut self, name: &str, value: impl Into<LuaValue>) -> Self {
+        self.globals.push((name.to_string(), value.into()));
+        self
+    }
+
+    /// Global tables are always written entry per line to keep generated files readable.
+    pub fn to_lua(&self) -> String {
+        self.globals.iter()
+            .map(|g| {
+                let value = match &g.1 {
+                    LuaValue::Table(table) if table.is_empty() == false => {
+                        let mut output = "{\n".to_string();
+                        for entry in table.entries.iter() {
+                            match &entry.0 {
+                                Some(key) => output += &format!("\t{} = {},\n", key.to_lua(), entry.1.to_lua(1)),
+                                None => output += &format!("\t{},\n", entry.1.to_lua(1))
+                            }
+                        }
+                        output.push('}');
+                        output
+                    },
+                    _=> g.1.to_lua(0)
+                };
+                format!("{} = {}\n", &g.0, value)
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Writes chunk into file with given name in given directory.
+    pub fn write(&self, dir: &PathBuf, file_name: &str) {
+        let mut file = std::fs::File::create(dir.join(file_name)).unwrap();
+        file.write_all(self.to_lua().as_bytes()).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal parser of lua subset produced by LuaChunk, used to check generated code can be read back.
+    struct Parser<'a> {
+        chars: std::iter::Peekable<std::str::Chars<'a>>
+    }
+
+    impl<'a> Parser<'a> {
+        fn new(s: &'a str) -> Self {
+            Parser {
+                chars: s.chars().peekable()
+            }
+        }
+
+        fn skip_spaces(&mut self) {
+            while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+                self.chars.next();
+            }
+        }
+
+        fn expect(&mut self, expected: char) {
+            self.skip_spaces();
+            assert_eq!(self.chars.next(), Some(expected));
+        }
+
+        fn name(&mut self) -> String {
+            self.skip_spaces();
+            let mut name = String::new();
+            while self.chars.peek().is_some_and(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                name.push(self.chars.next().unwrap());
+            }
+            name
+        }
+
+        fn string(&mut self) -> String {
+            self.expect('"');
+            let mut s = String::new();
+            loop {
+                match self.chars.next().unwrap() {
+                    '"' => return s,
+                    '\\' => {
+                        match self.chars.next().unwrap() {
+                            'n' => s.push('\n'),
+                            'r' => s.push('\r'),
+                            't' => s.push('\t'),
+                            d if d.is_ascii_digit() => {
+                                let mut code = d.to_string();
+                                while code.len() < 3 && self.chars.peek().is_some_and(|c| c.is_ascii_digit()) {
+                                    code.push(self.chars.next().unwrap());
+                                }
+                                s.push(char::from_u32(code.parse().unwrap()).unwrap());
+                            },
+                            c => s.push(c)
+                        }
+                    },
+                    c => s.push(c)
+                }
+            }
+        }
+
+        fn number(&mut self) -> LuaValue {
+            let mut number = String::new();
+            while self.chars.peek().is_some_and(|c| c.is_ascii_digit() || *c == '-' || *c == '.' || *c == 'e') {
+                number.push(self.chars.next().unwrap());
+            }
+            match number.contains('.') || number.contains('e') {
+                true => LuaValue::Number(number.parse().unwrap()),
+                false => LuaValue::Integer(number.parse().unwrap())
+            }
+        }
+
+        fn value(&mut self) -> LuaValue {
+            self.skip_spaces();
+            match *self.chars.peek().unwrap() {
+                '"' => LuaValue::String(self.string()),
+                '{' => LuaValue::Table(self.table()),
+                c if c.is_ascii_digit() || c == '-' => self.number(),
+                _=> {
+                    match self.name().as_str() {
+                        "nil" => LuaValue::Nil,
+                        "true" => LuaValue::Bool(true),
+                        "false" => LuaValue::Bool(false),
+                        name => LuaValue::Constant(name.to_string())
+                    }
+                }
+            }
+        }
+
+        fn table(&mut self) -> LuaTable {
+            self.expect('{');
+            let mut table = LuaTable::new();
+            loop {
+                self.skip_spaces();
+                match *self.chars.peek().unwrap() {
+                    '}' => {
+                        self.chars.next();
+                        return table;
+                    },
+                    ',' => {
+                        self.chars.next();
+                    },
+                    '[' => {
+                        self.chars.next();
+                        let key = match self.value() {
+                            LuaValue::String(s) => LuaKey::String(s),
+                            LuaValue::Constant(c) => LuaKey::Constant(c),
+                            LuaValue::Integer(i) => LuaKey::Integer(i),
+                            v => panic!("Unexpected key {:?}", v)
+                        };
+                        self.expect(']');
+                        self.expect('=');
+                        let value = self.value();
+                        table.insert(key, value);
+                    },
+                    c if c.is_ascii_alphabetic() || c == '_' => {
+                        // name = value or constant list item
+                        let value = self.value();
+                        self.skip_spaces();
+                        match (self.chars.peek(), value) {
+                            (Some('='), LuaValue::Constant(name)) => {
+                                self.chars.next();
+                                let value = self.value();
+                                table.insert(LuaKey::Name(name), value);
+                            },
+                            (_, value) => table.push(value)
+                        }
+                    },
+                    _=> {
+                        let value = self.value();
+                        table.push(value);
+                    }
+                }
+            }
+        }
+
+        fn chunk(&mut self) -> Vec<(String, LuaValue)> {
+            let mut globals = vec![];
+            loop {
+                self.skip_spaces();
+                if self.chars.peek().is_none() {
+                    return globals;
+                }
+                let name = self.name();
+                self.expect('=');
+                globals.push((name, self.value()));
+            }
+        }
+    }
+
+    fn parse_back(chunk: &LuaChunk) -> Vec<(String, LuaValue)> {
+        Parser::new(&chunk.to_lua()).chunk()
+    }
+
+    #[test]
+    fn strings_are_escaped() {
+        let tricky = "quote \" backslash \\ newline \n tab \t bell \u{7} end\\";
+        let chunk = LuaChunk::new().with("BTD_Test", tricky);
+        assert_eq!(parse_back(&chunk), vec![("BTD_Test".to_string(), LuaValue::from(tricky))]);
+    }
+
+    #[test]
+    fn control_character_before_digit_is_not_merged() {
+        let chunk = LuaChunk::new().with("BTD_Test", "\u{1}1");
+        assert_eq!(chunk.to_lua(), "BTD_Test = \"\\0011\"\n");
+        assert_eq!(parse_back(&chunk)[0].1, LuaValue::from("\u{1}1"));
+    }
+
+    #[test]
+    fn nested_tables_are_read_back() {
+        let table = LuaTable::new()
+            .with(LuaKey::String("btd_town_\"1\"".to_string()), LuaTable::new()
+                .with_field("rot", 3.0f32)
+                .with_field("x", 10)
+                .with_field("y", -5))
+            .with(LuaKey::Constant("PLAYER_1".to_string()), LuaTable::new()
+                .with_field("army", LuaTable::new()
+                    .with_field("id", LuaValue::Constant("CREATURE_PEASANT".to_string()))
+                    .with_field("count", 10u32))
+                .with_field("bonus", LuaValue::Nil)
+                .with_field("enabled", true))
+            .with(LuaKey::Integer(3), 0.25f64);
+        let mut list = LuaTable::new();
+        list.push(LuaValue::Constant("SKILL_LOGISTICS".to_string()));
+        list.push("text");
+        let chunk = LuaChunk::new()
+            .with("BTD_Towns", table.clone())
+            .with("BTD_List", list.clone())
+            .with("BTD_Empty", LuaTable::new());
+        assert_eq!(parse_back(&chunk), vec![
+            ("BTD_Towns".to_string(), LuaValue::Table(table)),
+            ("BTD_List".to_string(), LuaValue::Table(list)),
+            ("BTD_Empty".to_string(), LuaValue::Table(LuaTable::new()))
+        ]);
+    }
+
+    #[test]
+    fn incorrect_names_are_written_as_strings() {
+        let table = LuaTable::new()
+            .with_field("end", 1)
+            .with_field("with space", 2);
+        let chunk = LuaChunk::new().with("BTD_Test", table);
+        let parsed = parse_back(&chunk);
+        assert_eq!(parsed[0].1, LuaValue::Table(LuaTable::new()
+            .with(LuaKey::String("end".to_string()), 1)
+            .with(LuaKey::String("with space".to_string()), 2)
+        ));
+    }
+
+    #[test]
+    fn numbers_keep_their_types() {
+        let table = LuaTable::new()
+            .with_field("integer", 2)
+            .with_field("round_number", 2.0f64)
+            .with_field("number", 0.8f64);
+        let chunk = LuaChunk::new().with("BTD_Test", table.clone());
+        assert_eq!(parse_back(&chunk)[0].1, LuaValue::Table(table));
+    }
+
+    #[test]
+    fn json_values_keep_their_types() {
+        let json: serde_json::Value = serde_json::from_str(r#"{"flag": true, "count": 3, "ratio": 0.5, "name": "x", "list": [1, 2]}"#).unwrap();
+        let mut list = LuaTable::new();
+        list.push(1);
+        list.push(2);
+        // fields of json objects are sorted
+        assert_eq!(LuaValue::from(&json), LuaValue::Table(LuaTable::new()
+            .with_field("count", 3)
+            .with_field("flag", true)
+            .with_field("list", list)
+            .with_field("name", "x")
+            .with_field("ratio", 0.5f64)
+        ));
+    }
+}
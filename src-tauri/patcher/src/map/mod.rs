@@ -7,10 +7,12 @@
 
 pub mod template;
 
-use std::{path::PathBuf, collections::HashMap, io::{Read, BufReader}};
+use std::{path::PathBuf, collections::HashMap, io::Read};
 use quick_xml::{Reader, events::Event};
 use serde::{Serialize, Deserialize};
+use crate::text::Utf16Text;
 use self::template::{TemplateTransferable, TemplatesInfoModel, TemplateModeType, TemplateModeName};
+use crate::patch_strategy::{modes::definition::ModeParams, player::{PlayerStartInfo, PlayerHandicap, PlayerControlInfo, draft::HeroDraft}, creature::StacksScaling, treasure::TreasureRule, building::portals::PortalsRepairPolicy, artifact::ArtifactsBans, economy::EconomyRule, mechanics::{moon_calendar::MoonCalendarSpec, bans::MechanicsBans}, light::LightsSelection};
 
 /// Currently presented map settings(mb also better to turn this into enum?)
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -21,6 +23,26 @@ pub struct MapSettings {
     pub enable_new_arts: bool
 }
 
+/// Names of map settings, used where some logic depends on them(town schemes conditions for example).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MapSettingFlag {
+    UseNightLights,
+    OnlyNeutralWeeks,
+    DisableNeutralTownsDwells,
+    EnableNewArts
+}
+
+impl MapSettings {
+    pub fn is_set(&self, flag: &MapSettingFlag) -> bool {
+        match flag {
+            MapSettingFlag::UseNightLights => self.use_night_lights,
+            MapSettingFlag::OnlyNeutralWeeks => self.only_neutral_weeks,
+            MapSettingFlag::DisableNeutralTownsDwells => self.disable_neutral_towns_dwells,
+            MapSettingFlag::EnableNewArts => self.enable_new_arts
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct MapTeamsCount {
     #[serde(rename = "Item")]
@@ -57,10 +79,41 @@ pub struct Map {
     pub map_desc: PathBuf,
     /// modes can be added by user
     pub modes: HashMap<TemplateModeName, TemplateModeType>,
+    /// modes that are known only by their config definitions, with params values
+    pub custom_modes: HashMap<String, ModeParams>,
     /// size in tiles of this map.
     pub size: usize,
     /// information about teams of this map.
     pub teams_info: Vec<usize>,
+    /// name of template map was generated with, if it was detected.
+    pub template_name: Option<String>,
+    /// seed of this patch, written into patch info so scripts and seeded patch steps can share it.
+    pub seed: u32,
+    /// hero draft, if organisers use it.
+    pub hero_draft: Option<HeroDraft>,
+    /// races and starting heroes forced for players(by numbers of players).
+    /// Every per-player setting uses numbers of players' slots(positions of their PlayerIDs, so Player1 is 1), whether slots are active or not.
+    pub players_start_info: HashMap<usize, PlayerStartInfo>,
+    /// handicaps of players(by numbers of players).
+    pub handicaps: HashMap<usize, PlayerHandicap>,
+    /// control settings of player slots(by numbers of players).
+    pub players_control: HashMap<usize, PlayerControlInfo>,
+    /// strength scaling of neutral stacks.
+    pub stacks_scaling: StacksScaling,
+    /// rules of treasures rebalancing.
+    pub treasure_rules: Vec<TreasureRule>,
+    /// what to do with broken portals groups.
+    pub portals_repair_policy: PortalsRepairPolicy,
+    /// artifacts that can't be placed in map.
+    pub artifacts_bans: ArtifactsBans,
+    /// owners and production changes of mines and dwellings.
+    pub economy_rules: Vec<EconomyRule>,
+    /// banned and modified spells and skills.
+    pub mechanics_bans: MechanicsBans,
+    /// spec of generated moon calendar, if it is set calendar is generated instead of default one.
+    pub moon_calendar: Option<MoonCalendarSpec>,
+    /// how ambient light of map is selected.
+    pub lights_selection: LightsSelection,
     /// this map's additional settings.
     pub settings: MapSettings,
     /// directory that contains map.xdb file(for additional files writing)
@@ -88,8 +141,23 @@ impl Map {
             map_name: PathBuf::default(),
             map_desc: PathBuf::default(),
             modes: HashMap::new(),
+            custom_modes: HashMap::new(),
             size: 0,
             teams_info: vec![0, 0, 0, 0, 0, 0, 0, 0, 0],
+            template_name: None,
+            seed: rand::random(),
+            hero_draft: None,
+            players_start_info: HashMap::new(),
+            handicaps: HashMap::new(),
+            players_control: HashMap::new(),
+            stacks_scaling: StacksScaling::default(),
+            treasure_rules: vec![],
+            portals_repair_policy: PortalsRepairPolicy::default(),
+            artifacts_bans: ArtifactsBans::default(),
+            economy_rules: vec![],
+            mechanics_bans: MechanicsBans::default(),
+            moon_calendar: None,
+            lights_selection: LightsSelection::default(),
             settings: MapSettings::default(),
             main_dir: PathBuf::default(),
             game_mechanics_dir: PathBuf::default(),
@@ -142,9 +210,7 @@ impl Map {
 
     /// Detects template of map.
     pub fn detect_template(&mut self, possible_templates: &TemplatesInfoModel) -> Option<TemplateTransferable> {
-        let file = std::fs::File::open(&self.map_desc).unwrap();
-        let buf = BufReader::new(file);
-        let desc = utf16_reader::read_to_string(buf);
+        let desc = Utf16Text::read(&self.map_desc).unwrap().text;
         let s = possible_templates.templates.iter()
             .filter(|template| {
                 desc.contains(&template.name)
@@ -155,6 +221,7 @@ impl Map {
         );
         match s {
             Some(template) => {
+                self.template_name = Some(template.name.clone());
                 if template.main_mode.is_some() {
                     let main_mode_key = *template.main_mode.as_ref().unwrap();
                     let main_mode = template.possible_modes.as_ref().unwrap().first().unwrap().clone();
@@ -183,6 +250,61 @@ impl Map {
     pub fn get_mode(&self, label: &TemplateModeName) -> Option<&TemplateModeType> {
         self.modes.get(label)
     }
+
+    pub fn add_custom_mode(&mut self, name: String, params: ModeParams) {
+        self.custom_modes.insert(name, params);
+    }
+
+    pub fn remove_custom_mode(&mut self, name: &String) {
+        self.custom_modes.remove(name);
+    }
+
+    /// Reads all objects with given tag from map.xdb without patching them.
+    /// Useful when some patch needs to know about objects that can be placed after patched one(guards of banks for example).
+    /// Objects that can't be deserialized are skipped, errors of map file reading are returned.
+    pub fn read_objects<T: serde::de::DeserializeOwned>(&self, tag: &str) -> Result<Vec<T>, String> {
+        let mut objects = vec![];
+        let s = std::fs::read_to_string(&self.map_xdb).map_err(|e| e.to_string())?;
+        let mut buf = Vec::new();
+        let mut reader = Reader::from_str(&s);
+        reader.trim_text(true);
+        reader.expand_empty_elements(true);
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Err(e) => return Err(format!("Error at position {}: {:?}", reader.buffer_position(), e)),
+                Ok(Event::Eof) => break,
+                Ok(Event::Start(e)) => {
+                    if e.name().as_ref() == tag.as_bytes() {
+                        let text = reader.read_text(e.to_end().name()).map_err(|e| e.to_string())?.to_string();
+                        let object_de: Result<T, quick_xml::DeError> = quick_xml::de::from_str(&format!("<{}>{}</{}>", tag, &text, tag));
+                        match object_de {
+                            Ok(object) => objects.push(object),
+                            Err(e) => println!("Error deserializing {} while reading map objects: {}", tag, e.to_string())
+                        }
+                    }
+                }
+                _=> ()
+            }
+            buf.clear();
+        }
+        Ok(objects)
+    }
+
+    /// Names of all active modes, built-in ones are presented with their string form.
+    pub fn active_mode_names(&self) -> Vec<String> {
+        self.modes.keys()
+            .map(|m| m.to_string())
+            .chain(self.custom_modes.keys().cloned())
+            .collect()
+    }
+
+    /// Names and params of all active modes, built-in ones are presented with their string form.
+    pub fn active_modes_params(&self) -> Vec<(String, ModeParams)> {
+        self.modes.iter()
+            .map(|m| (m.0.to_string(), m.1.to_params()))
+            .chain(self.custom_modes.iter().map(|m| (m.0.clone(), m.1.clone())))
+            .collect()
+    }
 }
 
 /// Used to unpack base map archive.
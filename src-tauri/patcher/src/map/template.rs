@@ -2,7 +2,7 @@ use serde::{Serialize, Deserialize};
 use strum_macros::{EnumString, Display};
 use std::collections::HashMap;
 
-use crate::patch_strategy::modes::{economic::ResourceWinInfo, final_battle::FinalBattleTime};
+use crate::{patch_strategy::modes::{economic::ResourceWinInfo, final_battle::FinalBattleTime, definition::ModeParams}, lua::{LuaValue, LuaTable}};
 
 /// This mod contains structs to work with map temlates.
 
@@ -33,14 +33,44 @@ pub enum TemplateModeType {
 }
 
 impl TemplateModeType {
-    pub fn to_game_mode(&self) -> String {
+    pub fn to_game_mode(&self) -> LuaValue {
         match self {
-            TemplateModeType::CaptureObject(d) => format!("{{\n\tdelay = {}\n}}", d),
-            TemplateModeType::Economic(r) => format!("{{\n\tres_type = {:?},\n\tcount = {}\n}}", &r._type, r.count),
-            TemplateModeType::FinalBattle(t) => format!("{{\n\tmonth = {},\n\tweek = {},\n\tday = {}\n}}", t.month, t.week, t.day),
-            _=> "1".to_string()
+            TemplateModeType::CaptureObject(d) => LuaTable::new()
+                .with_field("delay", *d)
+                .into(),
+            TemplateModeType::Economic(r) => LuaTable::new()
+                .with_field("res_type", LuaValue::Constant(format!("{:?}", &r._type)))
+                .with_field("count", r.count)
+                .into(),
+            TemplateModeType::FinalBattle(t) => LuaTable::new()
+                .with_field("month", t.month)
+                .with_field("week", t.week)
+                .with_field("day", t.day)
+                .into(),
+            _=> LuaValue::Integer(1)
         }
     }
+
+    /// Values of mode named as params of its definition(see modes::definition), so built-in modes are handled like custom ones.
+    pub fn to_params(&self) -> ModeParams {
+        let mut params = ModeParams::new();
+        match self {
+            TemplateModeType::CaptureObject(d) => {
+                params.insert("delay".to_string(), (*d).into());
+            },
+            TemplateModeType::Economic(r) => {
+                params.insert("res_type".to_string(), format!("{:?}", &r._type).into());
+                params.insert("count".to_string(), r.count.into());
+            },
+            TemplateModeType::FinalBattle(t) => {
+                params.insert("month".to_string(), t.month.into());
+                params.insert("week".to_string(), t.week.into());
+                params.insert("day".to_string(), t.day.into());
+            },
+            _=> {}
+        }
+        params
+    }
 }
 
 /// Template is actually is a type and a string that used to recognize this type in map file.
@@ -0,0 +1,161 @@
+pub mod modifiers;
+pub mod table;
+
+use std::{path::PathBuf, collections::HashMap};
+use homm5_types::artifact::AdvMapArtifact;
+use serde::{Serialize, Deserialize};
+use crate::lua::{LuaTable, LuaKey, LuaValue, LuaChunk};
+use super::{PatchModifyable, GenerateLuaCode, PatchGroup};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ArtifactClass {
+    Minor,
+    Major,
+    Relic,
+    Ultimate
+}
+
+/// Model of artifacts deserialization.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArtifactInfo {
+    /// Game constant of artifact(ARTIFACT_...).
+    pub id: String,
+    pub shared: String,
+    pub class: ArtifactClass,
+    /// Artifacts introduced in BTD, they can appear only if new artifacts are enabled.
+    #[serde(default)]
+    pub is_new: bool
+}
+
+/// Artifacts bans of map. Banned artifacts are replaced with random allowed ones of class listed in replacements,
+/// with ones of same class if their class has no replacement, or removed if replacement is None.
+/// Bans, allowed artifacts and class limits are also applied to map's artifacts table(see table).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ArtifactsBans {
+    /// Game constants of banned artifacts.
+    pub artifacts: Vec<String>,
+    pub classes: Vec<ArtifactClass>,
+    pub replacements: HashMap<ArtifactClass, Option<ArtifactClass>>,
+    /// Game constants of artifacts that are allowed even if their class is banned or they are disabled in game's table.
+    #[serde(default)]
+    pub allowed: Vec<String>,
+    /// Max numbers of artifacts of classes that can be generated randomly.
+    #[serde(default)]
+    pub class_limits: HashMap<ArtifactClass, u32>
+}
+
+impl ArtifactsBans {
+    pub fn is_banned(&self, artifact: &ArtifactInfo) -> bool {
+        self.allowed.contains(&artifact.id) == false && (self.artifacts.contains(&artifact.id) || self.classes.contains(&artifact.class))
+    }
+}
+
+/// Provides information that can be used across different patches in ArtifactPatchesGroup.
+pub struct ArtifactInfoProvider {
+    artifacts: Vec<ArtifactInfo>
+}
+
+impl ArtifactInfoProvider {
+    pub fn new(config: &PathBuf) -> Self {
+        let artifacts_de: Result<Vec<ArtifactInfo>, String> = std::fs::read_to_string(config.join("artifacts.json"))
+            .map_err(|e| e.to_string())
+            .and_then(|artifacts_se| serde_json::from_str(&artifacts_se).map_err(|e| e.to_string()));
+        ArtifactInfoProvider { 
+            artifacts: artifacts_de.unwrap_or_else(|e| {
+                println!("Error reading artifacts info, artifacts bans won't work: {}", &e);
+                vec![]
+            })
+        }
+    }
+
+    /// Returns artifact info based on its shared string.
+    pub fn get_artifact(&self, shared: &String) -> Option<&ArtifactInfo> {
+        self.artifacts.iter().find(|a| a.shared == *shared)
+    }
+
+    /// Returns artifact info based on its game constant.
+    pub fn get_artifact_by_id(&self, id: &String) -> Option<&ArtifactInfo> {
+        self.artifacts.iter().find(|a| a.id == *id)
+    }
+
+    /// Returns artifacts of given class that can be placed in map.
+    pub fn get_allowed_artifacts(&self, class: &ArtifactClass, bans: &ArtifactsBans, new_arts_enabled: bool) -> Vec<&ArtifactInfo> {
+        self.artifacts.iter()
+            .filter(|a| a.class == *class && bans.is_banned(a) == false && (new_arts_enabled == true || a.is_new == false))
+            .collect()
+    }
+}
+
+/// ArtifactPatchesGroup combines all necessary patches for AdvMapArtifact game type.
+pub struct ArtifactPatchesGroup<'a> {
+    patches: Vec<&'a mut dyn PatchModifyable<Modifyable = AdvMapArtifact>>,
+    artifact_info_provider: &'a ArtifactInfoProvider,
+    artifacts_table: LuaTable,
+    is_removed: bool
+}
+
+impl<'a> ArtifactPatchesGroup<'a> {
+    pub fn new(aip: &'a ArtifactInfoProvider) -> Self {
+        ArtifactPatchesGroup { 
+            patches: vec![], 
+            artifact_info_provider: aip,
+            artifacts_table: LuaTable::new(),
+            is_removed: false
+        }
+    }
+
+    pub fn with_modifyable(mut self, patch: &'a mut dyn PatchModifyable<Modifyable = AdvMapArtifact>) -> Self {
+        self.patches.push(patch);
+        self
+    }
+}
+
+impl<'a> PatchGroup for ArtifactPatchesGroup<'a> {
+    fn run(&mut self, text: &String, writer: &mut quick_xml::Writer<&mut Vec<u8>>) {
+        let artifact_de: Result<AdvMapArtifact, quick_xml::DeError> = quick_xml::de::from_str(&format!("<AdvMapArtifact>{}</AdvMapArtifact>", text));
+        match artifact_de {
+            Ok(mut artifact) => {
+                for patch in self.patches.iter_mut() {
+                    patch.try_modify(&mut artifact);
+                    if patch.is_removal_requested() == true {
+                        self.is_removed = true;
+                        return;
+                    }
+                }
+                let no_xpointer_shared = artifact.shared.href.as_ref().unwrap().replace("#xpointer(/AdvMapArtifactShared)", "");
+                let id = match self.artifact_info_provider.get_artifact(&no_xpointer_shared) {
+                    Some(info) => LuaValue::Constant(info.id.clone()),
+                    None => LuaValue::Nil
+                };
+                self.artifacts_table.insert(LuaKey::String(artifact.name.clone()), LuaTable::new()
+                    .with_field("id", id)
+                    .with_field("x", artifact.pos.x)
+                    .with_field("y", artifact.pos.y)
+                );
+                writer.write_serializable("AdvMapArtifact", &artifact).unwrap();
+            },
+            Err(e) => println!("Error deserializing artifact: {}", e.to_string())
+        }
+    }
+
+    fn take_removal(&mut self) -> bool {
+        let is_removed = self.is_removed;
+        self.is_removed = false;
+        is_removed
+    }
+}
+
+impl<'a> GenerateLuaCode for ArtifactPatchesGroup<'a> {
+    fn to_lua(&self, path: &PathBuf) {
+        LuaChunk::new()
+            .with("BTD_Artifacts", self.artifacts_table.clone())
+            .write(path, "artifacts_info.lua");
+    }
+
+    fn generated_tables(&self) -> Vec<&'static str> {
+        match self.artifacts_table.is_empty() {
+            true => vec![],
+            false => vec!["BTD_Artifacts"]
+        }
+    }
+}
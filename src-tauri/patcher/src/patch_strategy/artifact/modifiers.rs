@@ -0,0 +1,105 @@
+/// Modifyable patch strategies for ArtifactPatchesGroup.
+
+use std::sync::RwLock;
+use homm5_types::{artifact::AdvMapArtifact, common::FileRef};
+use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+use serde::Serialize;
+use crate::{patch_strategy::PatchModifyable, report::PatchReport};
+use super::{ArtifactInfoProvider, ArtifactsBans};
+
+/// Applies script name to artifact.
+pub struct ArtifactNameApplier {
+    artifacts_count: u32
+}
+
+impl ArtifactNameApplier {
+    pub fn new() -> Self {
+        ArtifactNameApplier { 
+            artifacts_count: 0 
+        }
+    }
+}
+
+impl PatchModifyable for ArtifactNameApplier {
+    type Modifyable = AdvMapArtifact;
+
+    fn try_modify(&mut self, object: &mut Self::Modifyable) {
+        self.artifacts_count += 1;
+        object.name = format!("btd_artifact_{}", self.artifacts_count);
+    }
+}
+
+#[derive(Serialize)]
+struct ArtifactReplacementRecord {
+    artifact: String,
+    banned: String,
+    /// None if artifact was removed.
+    replacement: Option<String>
+}
+
+/// Replaces or removes banned artifacts placed in map.
+/// New artifacts can be used as replacements only if they are enabled for map.
+/// Replacements are picked with map's seed, so same seed gives same artifacts.
+pub struct BannedArtifactsReplacer<'a> {
+    artifact_info_provider: &'a ArtifactInfoProvider,
+    bans: &'a ArtifactsBans,
+    new_arts_enabled: bool,
+    report: &'a RwLock<PatchReport>,
+    rng: StdRng,
+    removal_requested: bool
+}
+
+impl<'a> BannedArtifactsReplacer<'a> {
+    pub fn new(aip: &'a ArtifactInfoProvider, bans: &'a ArtifactsBans, new_arts_enabled: bool, seed: u32, report: &'a RwLock<PatchReport>) -> Self {
+        BannedArtifactsReplacer {
+            artifact_info_provider: aip,
+            bans: bans,
+            new_arts_enabled: new_arts_enabled,
+            report: report,
+            rng: StdRng::seed_from_u64(seed as u64),
+            removal_requested: false
+        }
+    }
+}
+
+impl<'a> PatchModifyable for BannedArtifactsReplacer<'a> {
+    type Modifyable = AdvMapArtifact;
+
+    fn try_modify(&mut self, object: &mut Self::Modifyable) {
+        self.removal_requested = false;
+        let no_xpointer_shared = object.shared.href.as_ref().unwrap().replace("#xpointer(/AdvMapArtifactShared)", "");
+        let artifact = match self.artifact_info_provider.get_artifact(&no_xpointer_shared) {
+            Some(artifact) => artifact,
+            None => return
+        };
+        if self.bans.is_banned(artifact) == false {
+            return;
+        }
+        let replacement_class = match self.bans.replacements.get(&artifact.class) {
+            Some(class) => *class,
+            None => Some(artifact.class)
+        };
+        let replacement = replacement_class.and_then(|class| {
+            let allowed = self.artifact_info_provider.get_allowed_artifacts(&class, self.bans, self.new_arts_enabled);
+            allowed.choose(&mut self.rng).cloned()
+        });
+        match replacement {
+            Some(replacement) => {
+                object.shared = FileRef {
+                    href: Some(format!("{}#xpointer(/AdvMapArtifactShared)", &replacement.shared))
+                };
+            },
+            // nothing can replace artifact, so it is removed
+            None => self.removal_requested = true
+        }
+        self.report.write().unwrap().add("artifacts", &ArtifactReplacementRecord {
+            artifact: object.name.clone(),
+            banned: artifact.id.clone(),
+            replacement: replacement.map(|r| r.id.clone())
+        });
+    }
+
+    fn is_removal_requested(&self) -> bool {
+        self.removal_requested
+    }
+}
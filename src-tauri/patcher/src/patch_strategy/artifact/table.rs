@@ -0,0 +1,294 @@
+/// Map-level RefTables/Artifacts.xdb generation.
+
+use std::{path::PathBuf, io::Write, sync::RwLock};
+use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+use serde::{Serialize, Deserialize};
+use crate::{map::Map, patch_strategy::WriteAdditional, report::PatchReport};
+use super::{ArtifactClass, ArtifactInfoProvider};
+
+/// Artifacts table of map is based on game's one(it is extracted from game data while scanning files into adds/game/
+/// together with list of its artifacts). CanBeGeneratedToSell flags of artifacts are changed, so artifacts are allowed or banned
+/// for random generation according to map's bans, allowed list and class limits, and new artifacts are added if they are enabled.
+/// Table is written only if it differs from game's one.
+
+const GENERATION_ENABLED: &str = "<CanBeGeneratedToSell>true</CanBeGeneratedToSell>";
+const GENERATION_DISABLED: &str = "<CanBeGeneratedToSell>false</CanBeGeneratedToSell>";
+
+impl ArtifactClass {
+    pub fn from_xdb(class: &str) -> Option<ArtifactClass> {
+        match class {
+            "ARTF_CLASS_MINOR" => Some(ArtifactClass::Minor),
+            "ARTF_CLASS_MAJOR" => Some(ArtifactClass::Major),
+            "ARTF_CLASS_RELIC" => Some(ArtifactClass::Relic),
+            "ARTF_CLASS_GRAIL" => Some(ArtifactClass::Ultimate),
+            _=> None
+        }
+    }
+}
+
+/// Artifact of game's table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaseArtifact {
+    pub id: String,
+    pub class: Option<ArtifactClass>,
+    /// can artifact be generated randomly in game's table.
+    pub generatable: bool,
+    /// artifact isn't in game's table, it is taken from table of new artifacts(adds/common/Artifacts.xdb).
+    #[serde(default)]
+    pub is_new: bool
+}
+
+/// Artifact changed in map's table.
+#[derive(Debug, Serialize)]
+struct ArtifactTableRecord {
+    id: String,
+    generatable: bool
+}
+
+pub(crate) fn tag_value<'a>(text: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = text.find(&open)? + open.len();
+    let end = text[start..].find(&close)? + start;
+    Some(text[start..end].trim())
+}
+
+/// Returns positions of top level items of game's table, nested items are parts of them.
+pub(crate) fn table_items(table: &str) -> Vec<(usize, usize)> {
+    let mut items = vec![];
+    let mut position = 0;
+    while let Some(start) = table[position..].find("<Item>").map(|s| s + position) {
+        let mut depth = 0;
+        let mut cursor = start;
+        let end = loop {
+            let next_open = table[cursor..].find("<Item>").map(|s| s + cursor);
+            let next_close = match table[cursor..].find("</Item>").map(|s| s + cursor) {
+                Some(close) => close,
+                None => break None
+            };
+            match next_open {
+                Some(open) if open < next_close => {
+                    depth += 1;
+                    cursor = open + "<Item>".len();
+                },
+                _=> {
+                    depth -= 1;
+                    cursor = next_close + "</Item>".len();
+                    if depth == 0 {
+                        break Some(cursor);
+                    }
+                }
+            }
+        };
+        match end {
+            Some(end) => {
+                items.push((start, end));
+                position = end;
+            },
+            None => break
+        }
+    }
+    items
+}
+
+/// Reads artifacts of table with positions of their items. Used while scanning game files(see read_artifacts) and to write map's table.
+fn artifacts_items(table: &str) -> Vec<(usize, usize, BaseArtifact)> {
+    table_items(table).into_iter()
+        .filter_map(|(start, end)| {
+            let item = &table[start..end];
+            tag_value(item, "ID").map(|id| (start, end, BaseArtifact {
+                id: id.to_string(),
+                class: tag_value(item, "Type").and_then(|c| ArtifactClass::from_xdb(c)),
+                generatable: item.contains(GENERATION_ENABLED),
+                is_new: false
+            }))
+        })
+        .collect()
+}
+
+/// Returns artifacts of game's table. Game files scanning stores them into adds/game/artifacts.json, so patcher uses this list.
+pub fn read_artifacts(table: &str) -> Vec<BaseArtifact> {
+    artifacts_items(table).into_iter().map(|i| i.2).collect()
+}
+
+/// Provides game's artifacts table, artifacts found in it while scanning and new artifacts.
+pub struct ArtifactsTableProvider {
+    base_table: Option<String>,
+    /// artifacts of game's table from scanned data.
+    artifacts: Vec<BaseArtifact>,
+    /// items of new artifacts by their ids, they are added to map's table if new artifacts are enabled.
+    new_items: Vec<(BaseArtifact, String)>
+}
+
+impl ArtifactsTableProvider {
+    pub fn new(config: &PathBuf) -> Self {
+        let game_dir = config.join("adds\\game\\");
+        let base_table = std::fs::read_to_string(game_dir.join("GameMechanics\\RefTables\\Artifacts.xdb"));
+        if let Err(e) = &base_table {
+            println!("Error reading base artifacts table(game files must be scanned to extract it): {}", e.to_string());
+        }
+        let artifacts_de: Result<Vec<BaseArtifact>, String> = std::fs::read_to_string(game_dir.join("artifacts.json"))
+            .map_err(|e| e.to_string())
+            .and_then(|artifacts_se| serde_json::from_str(&artifacts_se).map_err(|e| e.to_string()));
+        let artifacts = artifacts_de.unwrap_or_else(|e| {
+            println!("Error reading scanned artifacts(game files must be scanned to get them): {}", &e);
+            vec![]
+        });
+        // new artifacts are items of table that was installed with enabled new artifacts before, game's artifacts are taken from game's table
+        let new_items = match std::fs::read_to_string(config.join("adds\\common\\Artifacts.xdb")) {
+            Ok(new_table) => {
+                artifacts_items(&new_table).into_iter()
+                    .filter(|item| artifacts.iter().any(|a| a.id == item.2.id) == false)
+                    .map(|(start, end, artifact)| (BaseArtifact { is_new: true, ..artifact }, new_table[start..end].to_string()))
+                    .collect()
+            },
+            Err(e) => {
+                println!("Error reading new artifacts table: {}", e.to_string());
+                vec![]
+            }
+        };
+        ArtifactsTableProvider {
+            base_table: base_table.ok(),
+            artifacts: artifacts,
+            new_items: new_items
+        }
+    }
+
+    /// Returns all artifacts of game's table and new ones.
+    pub fn get_artifacts(&self) -> Vec<BaseArtifact> {
+        self.artifacts.iter().cloned()
+            .chain(self.new_items.iter().map(|i| i.0.clone()))
+            .collect()
+    }
+
+    /// Decides which artifacts can be generated randomly in map. Explicitly allowed artifacts are always generatable,
+    /// banned ones never are, new artifacts are generatable only if they are enabled.
+    /// If class has limit, only limited number of its artifacts(picked with map's seed) stays generatable.
+    fn generatable(&self, artifacts: &Vec<BaseArtifact>, map: &Map, aip: &ArtifactInfoProvider) -> Vec<bool> {
+        let bans = &map.artifacts_bans;
+        let mut generatable: Vec<bool> = artifacts.iter()
+            .map(|a| {
+                let is_new = a.is_new || aip.get_artifact_by_id(&a.id).is_some_and(|info| info.is_new);
+                if bans.allowed.contains(&a.id) {
+                    true
+                }
+                else if bans.artifacts.contains(&a.id) || a.class.is_some_and(|c| bans.classes.contains(&c)) {
+                    false
+                }
+                else if is_new == true {
+                    map.settings.enable_new_arts
+                }
+                else {
+                    a.generatable
+                }
+            })
+            .collect();
+        let mut rng = StdRng::seed_from_u64(map.seed as u64);
+        // sorted, so picked artifacts don't depend on order of config
+        let mut limits: Vec<(&ArtifactClass, &u32)> = bans.class_limits.iter().collect();
+        limits.sort_by_key(|l| *l.0 as u8);
+        for (class, limit) in limits {
+            let (mut allowed, mut others): (Vec<usize>, Vec<usize>) = (0..artifacts.len())
+                .filter(|i| generatable[*i] == true && artifacts[*i].class == Some(*class))
+                .partition(|i| bans.allowed.contains(&artifacts[*i].id));
+            others.shuffle(&mut rng);
+            allowed.append(&mut others);
+            for index in allowed.into_iter().skip(*limit as usize) {
+                generatable[index] = false;
+            }
+        }
+        generatable
+    }
+
+    /// Sets generation flag of item, returns None if item has no flag.
+    fn set_generatable(item: &str, is_generatable: bool) -> Option<String> {
+        let (from, to) = match is_generatable {
+            true => (GENERATION_DISABLED, GENERATION_ENABLED),
+            false => (GENERATION_ENABLED, GENERATION_DISABLED)
+        };
+        match (item.contains(from), item.contains(to)) {
+            (true, _) => Some(item.replace(from, to)),
+            (false, true) => Some(item.to_string()),
+            _=> None
+        }
+    }
+
+    /// Creates map's table, returns None if it doesn't differ from game's one.
+    /// Game's artifacts are found in table by their ids, new artifacts are added after them if they are enabled.
+    pub fn generate(&self, map: &Map, aip: &ArtifactInfoProvider, report: &RwLock<PatchReport>) -> Option<String> {
+        let table = self.base_table.as_ref()?;
+        let artifacts = self.get_artifacts();
+        let generatable = self.generatable(&artifacts, map, aip);
+        let decisions: Vec<(&BaseArtifact, bool)> = artifacts.iter().zip(generatable.into_iter()).collect();
+        let mut map_table = String::new();
+        let mut position = 0;
+        let mut changed = 0;
+        for (start, end, item_artifact) in artifacts_items(table) {
+            map_table += &table[position..start];
+            let item_text = &table[start..end];
+            position = end;
+            let decision = decisions.iter().find(|d| d.0.id == item_artifact.id && d.0.is_new == false);
+            match decision {
+                Some((artifact, is_generatable)) if artifact.generatable != *is_generatable => {
+                    match Self::set_generatable(item_text, *is_generatable) {
+                        Some(item) => {
+                            map_table += &item;
+                            changed += 1;
+                            report.write().unwrap().add("artifacts_table", &ArtifactTableRecord {
+                                id: artifact.id.clone(),
+                                generatable: *is_generatable
+                            });
+                        },
+                        None => {
+                            println!("Artifact {} has no generation flag in table, it can't be changed", &artifact.id);
+                            map_table += item_text;
+                        }
+                    }
+                },
+                _=> map_table += item_text
+            }
+        }
+        if map.settings.enable_new_arts == true {
+            for (artifact, item_text) in self.new_items.iter() {
+                let is_generatable = decisions.iter().find(|d| d.0.id == artifact.id && d.0.is_new).is_some_and(|d| d.1);
+                map_table += &Self::set_generatable(item_text, is_generatable).unwrap_or(item_text.clone());
+                changed += 1;
+                report.write().unwrap().add("artifacts_table", &ArtifactTableRecord {
+                    id: artifact.id.clone(),
+                    generatable: is_generatable
+                });
+            }
+        }
+        map_table += &table[position..];
+        match changed {
+            0 => None,
+            _=> Some(map_table)
+        }
+    }
+}
+
+/// Writes generated artifacts table into map's GameMechanics/RefTables/.
+pub struct ArtifactsTableWriter<'a> {
+    table: Option<String>,
+    write_dir: &'a PathBuf
+}
+
+impl<'a> ArtifactsTableWriter<'a> {
+    pub fn new(table: Option<String>, dir: &'a PathBuf) -> Self {
+        ArtifactsTableWriter {
+            table: table,
+            write_dir: dir
+        }
+    }
+}
+
+impl<'a> WriteAdditional for ArtifactsTableWriter<'a> {
+    fn try_write(&self) {
+        if let Some(table) = &self.table {
+            let path_to = self.write_dir.join("RefTables\\Artifacts.xdb");
+            std::fs::create_dir_all(&path_to.parent().unwrap()).unwrap();
+            let mut file = std::fs::File::create(&path_to).unwrap();
+            file.write_all(table.as_bytes()).unwrap();
+        }
+    }
+}
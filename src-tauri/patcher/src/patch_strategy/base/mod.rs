@@ -14,11 +14,8 @@ impl PatchCreatable for CustomTeamsCreator {
 }
 
 
-const MAP_SCRIPT_FILES_NAMES: [&'static str; 2] = [
-    "MapScript.lua", "MapScript.xdb"
-];
-
-/// Creates main scripts files and writes its name into MapScript tag.
+/// Creates main script xdb file and writes its name into MapScript tag.
+/// MapScript.lua itself is assembled from modules by script::MapScriptAssembler.
 pub struct MapScriptCreator<'a> {
     config_path: &'a PathBuf,
     write_path: &'a PathBuf
@@ -43,9 +40,7 @@ impl<'a> PatchCreatable for MapScriptCreator<'a> {
 
 impl<'a> WriteAdditional for MapScriptCreator<'a> {
     fn try_write(&self) {
-        for file_name in MAP_SCRIPT_FILES_NAMES {
-            std::fs::copy(self.config_path.join(file_name), self.write_path.join(file_name)).unwrap();
-        }
+        std::fs::copy(self.config_path.join("MapScript.xdb"), self.write_path.join("MapScript.xdb")).unwrap();
     }
 }
 
@@ -2,7 +2,7 @@
 
 use homm5_types::building::{AdvMapBuilding, NewBuildingType};
 use crate::patch_strategy::PatchGetter;
-use super::{BuildingType, BuildingInfoProvider};
+use super::{BuildingType, BuildingInfoProvider, PORTAL_SHARED};
 
 pub struct BuildingGameInfo {
     pub _type: BuildingType,
@@ -45,7 +45,7 @@ impl<'a> PatchGetter for BuildingTypeDetector<'a> {
                 getter.type_name = Some(building_type.to_string());
             }
         }
-        else if no_xpointer_shared == "/MapObjects/Monolith_Two_Way.(AdvMapBuildingShared).xdb" {
+        else if no_xpointer_shared == PORTAL_SHARED {
             getter._type = BuildingType::Portal;
         }
     }
@@ -1,13 +1,19 @@
 pub mod modifiers;
 pub mod getters;
+pub mod portals;
 
-use std::{path::PathBuf, fs, io::Write};
+use std::path::PathBuf;
 use serde::{Serialize, Deserialize};
 use self::getters::BuildingGameInfo;
 
+use crate::{map::Map, lua::{LuaTable, LuaKey, LuaValue, LuaChunk}};
+
 use super::{GenerateLuaCode, PatchModifyable, PatchGetter, PatchGroup};
 use homm5_types::building::{AdvMapBuilding, NewBuildingType, BankType};
 
+/// Shared of two-way portal.
+pub const PORTAL_SHARED: &'static str = "/MapObjects/Monolith_Two_Way.(AdvMapBuildingShared).xdb";
+
 /// Model of new buildings deserialization
 #[derive(Debug, Deserialize, Serialize)]
 pub struct NewBuilding {
@@ -63,6 +69,18 @@ impl BuildingInfoProvider {
         self.buildings_info.iter().any(|b| b.shared == *shared)
     }
 
+    /// Returns positions of all banks of map(reads map before patching).
+    pub fn get_banks_positions(&self, map: &Map) -> Vec<(u32, u32)> {
+        let buildings = map.read_objects::<AdvMapBuilding>("AdvMapBuilding").unwrap_or_else(|e| {
+            println!("Error reading buildings to detect banks: {}", &e);
+            vec![]
+        });
+        buildings.iter()
+            .filter(|b| self.is_bank(&b.shared.href.as_ref().unwrap().replace("#xpointer(/AdvMapBuildingShared)", "")))
+            .map(|b| (b.pos.x as u32, b.pos.y as u32))
+            .collect()
+    }
+
     /// Returns type of bank based on its shared
     pub fn get_bank_type(&self, shared: &String) -> Option<BankType> {
         if let Some(bank) = self.banks_info.iter().find(|b| b.shared == *shared) {
@@ -88,10 +106,11 @@ impl BuildingInfoProvider {
 pub struct BuildingPatchesGroup<'a> {
     patches: Vec<&'a mut dyn PatchModifyable<Modifyable = AdvMapBuilding>>,
     getters: Vec<&'a mut dyn PatchGetter<Patchable = AdvMapBuilding, Additional = BuildingGameInfo>>,
-    banks_lua_string: Vec<String>,
-    new_buildings_lua_string: Vec<String>,
-    dwarven_mines_lua_string: Vec<String>,
-    portals_lua_string: Vec<String>
+    banks_table: LuaTable,
+    new_buildings_table: LuaTable,
+    dwarven_mines_table: LuaTable,
+    portals_table: LuaTable,
+    is_removed: bool
 }
 
 impl<'a> BuildingPatchesGroup<'a> {
@@ -99,10 +118,11 @@ impl<'a> BuildingPatchesGroup<'a> {
         BuildingPatchesGroup { 
             patches: vec![], 
             getters: vec![], 
-            banks_lua_string: vec![], 
-            new_buildings_lua_string: vec![], 
-            dwarven_mines_lua_string: vec![], 
-            portals_lua_string: vec![] 
+            banks_table: LuaTable::new(), 
+            new_buildings_table: LuaTable::new(), 
+            dwarven_mines_table: LuaTable::new(), 
+            portals_table: LuaTable::new(),
+            is_removed: false
         }
     }
 
@@ -128,33 +148,28 @@ impl<'a> PatchGroup for BuildingPatchesGroup<'a> {
                 };
                 for patch in self.patches.iter_mut() {
                     patch.try_modify(&mut building);
+                    if patch.is_removal_requested() == true {
+                        self.is_removed = true;
+                        return;
+                    }
                 }
                 for getter in self.getters.iter_mut() {
                     getter.try_get(&building, &mut building_game_info);
                 }
+                let name_key = LuaKey::String(building.name.clone());
                 match building_game_info._type {
                     BuildingType::Bank => {
-                        self.banks_lua_string.push(
-                            format!("\t[\"{}\"] = {},\n", &building.name, &building_game_info.type_name.unwrap())
-                        );
+                        self.banks_table.insert(name_key, LuaValue::Constant(building_game_info.type_name.unwrap()));
                     },
                     BuildingType::NewBuilding => {
-                        self.new_buildings_lua_string.push(
-                            format!("\t[\"{}\"] = {},\n", &building.name, &building_game_info.type_name.unwrap())
-                        );
+                        self.new_buildings_table.insert(name_key, LuaValue::Constant(building_game_info.type_name.unwrap()));
                     },
                     BuildingType::DwarvenMine => {
-                        self.new_buildings_lua_string.push(
-                            format!("\t[\"{}\"] = {},\n", &building.name, &building_game_info.type_name.unwrap())
-                        );
-                        self.dwarven_mines_lua_string.push(
-                            format!("\t[\"{}\"] = {},\n", &building.name, &building.rot)
-                        );
+                        self.new_buildings_table.insert(name_key.clone(), LuaValue::Constant(building_game_info.type_name.unwrap()));
+                        self.dwarven_mines_table.insert(name_key, building.rot);
                     },
                     BuildingType::Portal => {
-                        self.portals_lua_string.push(
-                            format!("\t[\"{}\"] = {},\n", &building.name, &building.group_id)
-                        );
+                        self.portals_table.insert(name_key, building.group_id);
                     },
                     _=> {}
                 }
@@ -165,31 +180,34 @@ impl<'a> PatchGroup for BuildingPatchesGroup<'a> {
             }
         }
     }
+
+    fn take_removal(&mut self) -> bool {
+        let is_removed = self.is_removed;
+        self.is_removed = false;
+        is_removed
+    }
 }
 
 impl<'a> GenerateLuaCode for BuildingPatchesGroup<'a> {
     fn to_lua(&self, path: &PathBuf) {
-        let mut generated_str = String::from("BTD_BanksInfo = \n{\n");
-        for s in self.banks_lua_string.iter() {
-            generated_str += s;
-        }
-        generated_str.push_str("}\n\n");
-        generated_str.push_str("BTD_NewObjects = \n{\n");
-        for s in self.new_buildings_lua_string.iter() {
-            generated_str += s;
-        }
-        generated_str.push_str("}\n\n");
-        generated_str.push_str("BTD_DwarvenMinesRots = \n{\n");
-        for s in self.dwarven_mines_lua_string.iter() {
-            generated_str += s;
-        }
-        generated_str.push_str("}\n\n");
-        generated_str.push_str("BTD_Portals = \n{\n");
-        for s in self.portals_lua_string.iter() {
-            generated_str += s;
-        }
-        generated_str.push_str("}\n\n");
-        let mut out_file = fs::File::create(path.join("buildings_info.lua")).unwrap();
-        out_file.write_all(&mut generated_str.as_bytes()).unwrap();
+        LuaChunk::new()
+            .with("BTD_BanksInfo", self.banks_table.clone())
+            .with("BTD_NewObjects", self.new_buildings_table.clone())
+            .with("BTD_DwarvenMinesRots", self.dwarven_mines_table.clone())
+            .with("BTD_Portals", self.portals_table.clone())
+            .write(path, "buildings_info.lua");
     }
-}
\ No newline at end of file
+
+    fn generated_tables(&self) -> Vec<&'static str> {
+        [
+            ("BTD_BanksInfo", &self.banks_table), 
+            ("BTD_NewObjects", &self.new_buildings_table), 
+            ("BTD_DwarvenMinesRots", &self.dwarven_mines_table), 
+            ("BTD_Portals", &self.portals_table)
+        ]
+            .into_iter()
+            .filter(|t| t.1.is_empty() == false)
+            .map(|t| t.0)
+            .collect()
+    }
+}
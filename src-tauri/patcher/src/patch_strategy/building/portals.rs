@@ -0,0 +1,141 @@
+use std::{collections::BTreeMap, sync::RwLock};
+use homm5_types::building::AdvMapBuilding;
+use serde::{Serialize, Deserialize};
+
+use crate::{map::Map, patch_strategy::PatchModifyable, report::PatchReport};
+use super::PORTAL_SHARED;
+
+/// Two-way portals must be linked in pairs by their group id, scripts using BTD_Portals expect it.
+/// Portals are analyzed before patching, broken groups(with one or more than two portals) are reported
+/// and can be repaired or removed by PortalsRepairer.
+
+/// What to do with broken portal groups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum PortalsRepairPolicy {
+    /// Only report broken groups.
+    #[default]
+    Report,
+    /// Portals of broken groups are paired again, most distant ones first, cause portals are meant to connect distant areas.
+    /// Portal that left without pair is removed.
+    RepairByDistance,
+    /// Remove all portals of broken groups.
+    Remove
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PortalDecision {
+    Keep,
+    Regroup(u32),
+    Remove
+}
+
+#[derive(Serialize)]
+struct BrokenPortalGroupRecord {
+    group_id: u32,
+    positions: Vec<(u32, u32)>,
+    policy: PortalsRepairPolicy
+}
+
+/// Result of portals analysis, decisions are stored in order portals are placed in map.
+pub struct PortalsAnalysis {
+    decisions: Vec<PortalDecision>
+}
+
+impl PortalsAnalysis {
+    /// Reads portals of map, finds broken groups, records them in report and decides what to do with every portal.
+    pub fn new(map: &Map, policy: PortalsRepairPolicy, report: &RwLock<PatchReport>) -> Self {
+        let buildings = map.read_objects::<AdvMapBuilding>("AdvMapBuilding").unwrap_or_else(|e| {
+            println!("Error reading buildings to analyze portals: {}", &e);
+            vec![]
+        });
+        let portals: Vec<AdvMapBuilding> = buildings.into_iter()
+            .filter(|b| b.shared.href.as_ref().is_some_and(|href| href.replace("#xpointer(/AdvMapBuildingShared)", "") == PORTAL_SHARED))
+            .collect();
+        let mut groups: BTreeMap<u32, Vec<usize>> = BTreeMap::new();
+        for portal in portals.iter().enumerate() {
+            groups.entry(portal.1.group_id).or_insert(vec![]).push(portal.0);
+        }
+        let mut decisions = vec![PortalDecision::Keep; portals.len()];
+        let mut broken_portals = vec![];
+        for group in groups.iter().filter(|g| g.1.len() != 2) {
+            report.write().unwrap().add("broken_portals", &BrokenPortalGroupRecord {
+                group_id: *group.0,
+                positions: group.1.iter().map(|p| (portals[*p].pos.x as u32, portals[*p].pos.y as u32)).collect(),
+                policy: policy
+            });
+            broken_portals.extend(group.1.iter().cloned());
+        }
+        match policy {
+            PortalsRepairPolicy::Report => {},
+            PortalsRepairPolicy::Remove => {
+                for portal in broken_portals {
+                    decisions[portal] = PortalDecision::Remove;
+                }
+            },
+            PortalsRepairPolicy::RepairByDistance => {
+                let mut free_group_id = groups.keys().max().map_or(0, |id| id + 1);
+                let distance = |first: usize, second: usize| {
+                    let dx = portals[first].pos.x as f32 - portals[second].pos.x as f32;
+                    let dy = portals[first].pos.y as f32 - portals[second].pos.y as f32;
+                    dx * dx + dy * dy
+                };
+                while broken_portals.len() > 1 {
+                    let first = broken_portals.remove(0);
+                    let most_distant = broken_portals.iter().enumerate()
+                        .max_by(|a, b| distance(first, *a.1).total_cmp(&distance(first, *b.1)))
+                        .map(|p| p.0)
+                        .unwrap();
+                    let second = broken_portals.remove(most_distant);
+                    decisions[first] = PortalDecision::Regroup(free_group_id);
+                    decisions[second] = PortalDecision::Regroup(free_group_id);
+                    free_group_id += 1;
+                }
+                if let Some(portal) = broken_portals.pop() {
+                    decisions[portal] = PortalDecision::Remove;
+                }
+            }
+        }
+        PortalsAnalysis { 
+            decisions: decisions 
+        }
+    }
+}
+
+/// Applies decisions of portals analysis.
+pub struct PortalsRepairer<'a> {
+    analysis: &'a PortalsAnalysis,
+    portals_count: usize,
+    removal_requested: bool
+}
+
+impl<'a> PortalsRepairer<'a> {
+    pub fn new(analysis: &'a PortalsAnalysis) -> Self {
+        PortalsRepairer { 
+            analysis: analysis, 
+            portals_count: 0,
+            removal_requested: false
+        }
+    }
+}
+
+impl<'a> PatchModifyable for PortalsRepairer<'a> {
+    type Modifyable = AdvMapBuilding;
+
+    fn try_modify(&mut self, object: &mut Self::Modifyable) {
+        self.removal_requested = false;
+        let no_xpointer_shared = object.shared.href.as_ref().unwrap().replace("#xpointer(/AdvMapBuildingShared)", "");
+        if no_xpointer_shared != PORTAL_SHARED {
+            return;
+        }
+        match self.analysis.decisions.get(self.portals_count) {
+            Some(PortalDecision::Regroup(group_id)) => object.group_id = *group_id,
+            Some(PortalDecision::Remove) => self.removal_requested = true,
+            _=> {}
+        }
+        self.portals_count += 1;
+    }
+
+    fn is_removal_requested(&self) -> bool {
+        self.removal_requested
+    }
+}
@@ -1,21 +1,81 @@
 pub mod modifiers;
 
-use std::io::Write;
+use std::{path::PathBuf, collections::HashMap};
 use homm5_types::creature::AdvMapMonster;
+use serde::{Serialize, Deserialize};
+use crate::lua::{LuaTable, LuaKey, LuaChunk};
 use super::{PatchModifyable, GenerateLuaCode, PatchGroup};
 
+/// Rule that changes strength of some neutral stacks in addition to map-wide multiplier.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum StackScalingRule {
+    /// Stacks of creatures of given tier.
+    Tier {
+        tier: u8,
+        multiplier: f32
+    },
+    /// Stacks placed not further than radius tiles from any bank.
+    BankGuards {
+        radius: u32,
+        multiplier: f32
+    }
+}
+
+/// Configures strength of neutral stacks of map. Multipliers of all matching rules are multiplied with map-wide one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StacksScaling {
+    pub multiplier: f32,
+    pub rules: Vec<StackScalingRule>
+}
+
+impl Default for StacksScaling {
+    fn default() -> Self {
+        StacksScaling { 
+            multiplier: 1.0, 
+            rules: vec![] 
+        }
+    }
+}
+
+/// Provides information about creatures that can be used in CreaturePatchesGroup.
+pub struct CreatureInfoProvider {
+    /// maps creatures shareds to their tiers.
+    tiers: HashMap<String, u8>
+}
+
+impl CreatureInfoProvider {
+    pub fn new(config: &PathBuf) -> Self {
+        let tiers_de: Result<HashMap<String, u8>, String> = std::fs::read_to_string(config.join("creatures_tiers.json"))
+            .map_err(|e| e.to_string())
+            .and_then(|tiers_se| serde_json::from_str(&tiers_se).map_err(|e| e.to_string()));
+        CreatureInfoProvider {
+            tiers: tiers_de.unwrap_or_else(|e| {
+                println!("Error reading creatures tiers, tier rules won't work: {}", &e);
+                HashMap::new()
+            })
+        }
+    }
+
+    /// Returns tier of creature based on its shared.
+    pub fn get_tier(&self, shared: &String) -> Option<u8> {
+        self.tiers.get(shared).cloned()
+    }
+}
+
 /// CreaturePatchesGroup combines all necessary patches for AdvMapMonster game type.
 pub struct CreaturePatchesGroup<'a> {
     patches: Vec<&'a mut dyn PatchModifyable<Modifyable = AdvMapMonster>>,
     // getters: Vec<&'a dyn PatchGetter<Patchable = AdvMapMonster, Additional = CreatureGameInfo>>,
-    lua_strings: Vec<String>
+    stacks_table: LuaTable,
+    is_removed: bool
 }
 
 impl<'a> CreaturePatchesGroup<'a> {
     pub fn new() -> Self {
         CreaturePatchesGroup { 
             patches: vec![], 
-            lua_strings: vec![] 
+            stacks_table: LuaTable::new(),
+            is_removed: false
         }
     }
 
@@ -32,30 +92,39 @@ impl<'a> PatchGroup for CreaturePatchesGroup<'a> {
             Ok(mut creature) => {
                 for patch in self.patches.iter_mut() {
                     patch.try_modify(&mut creature);
+                    if patch.is_removal_requested() == true {
+                        self.is_removed = true;
+                        return;
+                    }
                 }
-                self.lua_strings.push(
-                    format!(
-                        "\t[\"{}\"] = {{ x = {}, y = {} }},\n", 
-                        creature.name.as_ref().unwrap(),
-                        creature.pos.x,
-                        creature.pos.y
-                    )
+                self.stacks_table.insert(LuaKey::String(creature.name.clone().unwrap()), LuaTable::new()
+                    .with_field("x", creature.pos.x)
+                    .with_field("y", creature.pos.y)
                 );
                 writer.write_serializable("AdvMapMonster", &creature).unwrap();
             },
             Err(e) => println!("Error deserializing creature: {}", e.to_string())
         }
     }
+
+    fn take_removal(&mut self) -> bool {
+        let is_removed = self.is_removed;
+        self.is_removed = false;
+        is_removed
+    }
 }
 
 impl<'a> GenerateLuaCode for CreaturePatchesGroup<'a> {
-    fn to_lua(&self, path: & std::path::PathBuf) {
-        let mut output = "BTD_Stacks = {\n".to_string();
-        for s in self.lua_strings.iter() {
-            output += s;
+    fn to_lua(&self, path: &PathBuf) {
+        LuaChunk::new()
+            .with("BTD_Stacks", self.stacks_table.clone())
+            .write(path, "stacks.lua");
+    }
+
+    fn generated_tables(&self) -> Vec<&'static str> {
+        match self.stacks_table.is_empty() {
+            true => vec![],
+            false => vec!["BTD_Stacks"]
         }
-        output.push_str("}");
-        let mut file = std::fs::File::create(path.join("stacks.lua")).unwrap();
-        file.write_all(&output.as_bytes()).unwrap();
     }
 }
\ No newline at end of file
@@ -1,7 +1,10 @@
 /// Modifyable patch strategies for CreaturePatchesGroup.
 
+use std::sync::RwLock;
 use homm5_types::creature::AdvMapMonster;
-use crate::patch_strategy::PatchModifyable;
+use serde::Serialize;
+use crate::{patch_strategy::PatchModifyable, report::PatchReport};
+use super::{StacksScaling, StackScalingRule, CreatureInfoProvider};
 
 /// Apllies script names for creatures.
 pub struct CreatureNameApplier {
@@ -37,3 +40,85 @@ impl PatchModifyable for AdditionalStackFixer {
         }
     }
 }
+
+/// Original and scaled amounts of stack, main stack goes first.
+#[derive(Serialize)]
+struct StackScalingRecord {
+    stack: String,
+    multiplier: f32,
+    original: Vec<u32>,
+    scaled: Vec<u32>
+}
+
+/// Scales amounts of neutral stacks by configured rules and records original amounts in patch report.
+/// Must be applied after CreatureNameApplier to make report readable.
+pub struct NeutralStackScaler<'a> {
+    scaling: &'a StacksScaling,
+    creature_info_provider: &'a CreatureInfoProvider,
+    /// positions of all banks of map.
+    banks_positions: Vec<(u32, u32)>,
+    report: &'a RwLock<PatchReport>
+}
+
+impl<'a> NeutralStackScaler<'a> {
+    pub fn new(scaling: &'a StacksScaling, cip: &'a CreatureInfoProvider, banks_positions: Vec<(u32, u32)>, report: &'a RwLock<PatchReport>) -> Self {
+        NeutralStackScaler {
+            scaling: scaling,
+            creature_info_provider: cip,
+            banks_positions: banks_positions,
+            report: report
+        }
+    }
+
+    fn get_multiplier(&self, object: &AdvMapMonster) -> f32 {
+        let mut multiplier = self.scaling.multiplier;
+        let no_xpointer_shared = object.shared.href.as_ref().unwrap().replace("#xpointer(/AdvMapMonsterShared)", "");
+        let (x, y) = (object.pos.x as u32, object.pos.y as u32);
+        for rule in self.scaling.rules.iter() {
+            match rule {
+                StackScalingRule::Tier { tier, multiplier: tier_multiplier } => {
+                    if self.creature_info_provider.get_tier(&no_xpointer_shared) == Some(*tier) {
+                        multiplier *= tier_multiplier;
+                    }
+                },
+                StackScalingRule::BankGuards { radius, multiplier: guards_multiplier } => {
+                    if self.banks_positions.iter().any(|bank| bank.0.abs_diff(x) <= *radius && bank.1.abs_diff(y) <= *radius) {
+                        multiplier *= guards_multiplier;
+                    }
+                }
+            }
+        }
+        multiplier
+    }
+}
+
+fn scale_amount(amount: u32, multiplier: f32) -> u32 {
+    ((amount as f32 * multiplier).round() as u32).max(1)
+}
+
+impl<'a> PatchModifyable for NeutralStackScaler<'a> {
+    type Modifyable = AdvMapMonster;
+
+    fn try_modify(&mut self, object: &mut Self::Modifyable) {
+        let multiplier = self.get_multiplier(object);
+        if multiplier == 1.0 {
+            return;
+        }
+        let mut record = StackScalingRecord {
+            stack: object.name.clone().unwrap_or_default(),
+            multiplier: multiplier,
+            original: vec![object.amount],
+            scaled: vec![]
+        };
+        object.amount = scale_amount(object.amount, multiplier);
+        record.scaled.push(object.amount);
+        if let Some(stacks) = object.additional_stacks.as_mut().and_then(|s| s.items.as_mut()) {
+            for stack in stacks.iter_mut() {
+                record.original.push(stack.amount);
+                stack.amount = scale_amount(stack.amount, multiplier);
+                record.scaled.push(stack.amount);
+            }
+        }
+        self.report.write().unwrap().add("neutral_stacks", &record);
+    }
+}
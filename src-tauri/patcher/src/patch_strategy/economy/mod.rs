@@ -0,0 +1,280 @@
+pub mod modifiers;
+
+use std::path::PathBuf;
+use homm5_types::{building::{AdvMapMine, AdvMapDwelling}, town::TownType, player::PlayerID};
+use serde::{Serialize, Deserialize};
+use strum::IntoEnumIterator;
+use crate::lua::{LuaTable, LuaKey, LuaValue, LuaChunk};
+use super::{PatchModifyable, GenerateLuaCode, PatchGroup};
+
+/// Mines and creature dwellings placed outside of towns.
+/// They are classified by configs(mines_types.json and dwellings_types.json) and exported to mines_info.lua and dwellings_info.lua
+/// with their owners and entrance tiles, production changes are applied by map script(adds/scripts/economy.lua).
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MineResource {
+    WOOD,
+    ORE,
+    MERCURY,
+    CRYSTAL,
+    SULFUR,
+    GEM,
+    GOLD
+}
+
+/// Offset of entrance tile from object's position when object isn't rotated.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct EntranceOffset {
+    pub x: i32,
+    pub y: i32
+}
+
+/// Model of mines types deserialization.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MineInfo {
+    pub resource: MineResource,
+    pub shared: String,
+    pub entrance: EntranceOffset
+}
+
+/// Model of dwellings types deserialization.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DwellingInfo {
+    pub town: TownType,
+    pub tier: u8,
+    /// Game constant of creature dwelling hires(CREATURE_...), production of dwelling can't be changed without it.
+    #[serde(default)]
+    pub creature: Option<String>,
+    pub shared: String,
+    pub entrance: EntranceOffset
+}
+
+/// Objects rule can be applied to. None means objects of any resource/tier.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum EconomyRuleTarget {
+    Mine(Option<MineResource>),
+    Dwelling(Option<u8>)
+}
+
+/// Changes owner and(or) production of mines or dwellings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EconomyRule {
+    pub target: EconomyRuleTarget,
+    pub owner: Option<PlayerID>,
+    /// Applied by map script: resources mines give every day, creatures available in dwellings every week.
+    pub production_multiplier: Option<f32>
+}
+
+impl EconomyRule {
+    fn is_for_mine(&self, mine: &MineInfo) -> bool {
+        match &self.target {
+            EconomyRuleTarget::Mine(resource) => resource.is_none() || *resource == Some(mine.resource),
+            _=> false
+        }
+    }
+
+    fn is_for_dwelling(&self, dwelling: &DwellingInfo) -> bool {
+        match &self.target {
+            EconomyRuleTarget::Dwelling(tier) => tier.is_none() || *tier == Some(dwelling.tier),
+            _=> false
+        }
+    }
+}
+
+/// Provides information that can be used across different patches of mines and dwellings groups.
+pub struct EconomyInfoProvider {
+    mines_info: Vec<MineInfo>,
+    dwellings_info: Vec<DwellingInfo>
+}
+
+impl EconomyInfoProvider {
+    pub fn new(config: &PathBuf) -> Self {
+        let mines_de: Result<Vec<MineInfo>, String> = std::fs::read_to_string(config.join("mines_types.json"))
+            .map_err(|e| e.to_string())
+            .and_then(|mines_se| serde_json::from_str(&mines_se).map_err(|e| e.to_string()));
+        let dwellings_de: Result<Vec<DwellingInfo>, String> = std::fs::read_to_string(config.join("dwellings_types.json"))
+            .map_err(|e| e.to_string())
+            .and_then(|dwellings_se| serde_json::from_str(&dwellings_se).map_err(|e| e.to_string()));
+        EconomyInfoProvider { 
+            mines_info: mines_de.unwrap_or_else(|e| {
+                println!("Error reading mines types, mines won't be classified: {}", &e);
+                vec![]
+            }), 
+            dwellings_info: dwellings_de.unwrap_or_else(|e| {
+                println!("Error reading dwellings types, dwellings won't be classified: {}", &e);
+                vec![]
+            })
+        }
+    }
+
+    /// Returns mine info based on its shared.
+    pub fn get_mine(&self, shared: &String) -> Option<&MineInfo> {
+        self.mines_info.iter().find(|m| m.shared == *shared)
+    }
+
+    /// Returns dwelling info based on its shared.
+    pub fn get_dwelling(&self, shared: &String) -> Option<&DwellingInfo> {
+        self.dwellings_info.iter().find(|d| d.shared == *shared)
+    }
+}
+
+/// Rotates entrance offset same way as towns active tiles are rotated.
+fn entrance_tile(x: i32, y: i32, rot: f32, offset: &EntranceOffset) -> (i32, i32) {
+    let rot_rounded = rot.round();
+    if rot_rounded == 5.0 {
+        (x + offset.y, y - offset.x)
+    }
+    else if rot_rounded == 3.0 {
+        (x - offset.x, y - offset.y)
+    }
+    else if rot_rounded == 2.0 {
+        (x - offset.y, y + offset.x)
+    }
+    else {
+        (x + offset.x, y + offset.y)
+    }
+}
+
+fn player_to_lua(player: &PlayerID) -> LuaValue {
+    match PlayerID::iter().position(|p| p == *player) {
+        Some(number) if number > 0 => LuaValue::Constant(format!("PLAYER_{}", number)),
+        _=> LuaValue::Constant("PLAYER_NONE".to_string())
+    }
+}
+
+/// Production multiplier of last matching rule, 1 if there are no such rules.
+fn production_multiplier<'a>(mut rules: impl Iterator<Item = &'a EconomyRule>) -> f32 {
+    rules.find_map(|r| r.production_multiplier).unwrap_or(1.0)
+}
+
+/// MinePatchesGroup combines all necessary patches for AdvMapMine game type.
+pub struct MinePatchesGroup<'a> {
+    patches: Vec<&'a mut dyn PatchModifyable<Modifyable = AdvMapMine>>,
+    economy_info_provider: &'a EconomyInfoProvider,
+    rules: &'a Vec<EconomyRule>,
+    lua_table: LuaTable
+}
+
+impl<'a> MinePatchesGroup<'a> {
+    pub fn new(eip: &'a EconomyInfoProvider, rules: &'a Vec<EconomyRule>) -> Self {
+        MinePatchesGroup { 
+            patches: vec![], 
+            economy_info_provider: eip, 
+            rules: rules, 
+            lua_table: LuaTable::new()
+        }
+    }
+
+    pub fn with_modifyable(mut self, patch: &'a mut dyn PatchModifyable<Modifyable = AdvMapMine>) -> Self {
+        self.patches.push(patch);
+        self
+    }
+}
+
+impl<'a> PatchGroup for MinePatchesGroup<'a> {
+    fn run(&mut self, text: &String, writer: &mut quick_xml::Writer<&mut Vec<u8>>) {
+        let mine_de: Result<AdvMapMine, quick_xml::DeError> = quick_xml::de::from_str(&format!("<AdvMapMine>{}</AdvMapMine>", text));
+        match mine_de {
+            Ok(mut mine) => {
+                for patch in self.patches.iter_mut() {
+                    patch.try_modify(&mut mine);
+                }
+                let no_xpointer_shared = mine.shared.href.as_ref().unwrap().replace("#xpointer(/AdvMapMineShared)", "");
+                if let Some(info) = self.economy_info_provider.get_mine(&no_xpointer_shared) {
+                    let entrance = entrance_tile(mine.pos.x, mine.pos.y, mine.rot, &info.entrance);
+                    self.lua_table.insert(LuaKey::String(mine.name.clone()), LuaTable::new()
+                        .with_field("resource", LuaValue::Constant(format!("{:?}", info.resource)))
+                        .with_field("owner", player_to_lua(&mine.player_id))
+                        .with_field("x", entrance.0)
+                        .with_field("y", entrance.1)
+                        .with_field("production", production_multiplier(self.rules.iter().rev().filter(|r| r.is_for_mine(info))))
+                    );
+                }
+                writer.write_serializable("AdvMapMine", &mine).unwrap();
+            },
+            Err(e) => println!("Error deserializing mine: {}", e.to_string())
+        }
+    }
+}
+
+impl<'a> GenerateLuaCode for MinePatchesGroup<'a> {
+    fn to_lua(&self, path: &PathBuf) {
+        LuaChunk::new()
+            .with("BTD_Mines", self.lua_table.clone())
+            .write(path, "mines_info.lua");
+    }
+
+    fn generated_tables(&self) -> Vec<&'static str> {
+        match self.lua_table.is_empty() {
+            true => vec![],
+            false => vec!["BTD_Mines"]
+        }
+    }
+}
+
+/// DwellingPatchesGroup combines all necessary patches for AdvMapDwelling game type.
+pub struct DwellingPatchesGroup<'a> {
+    patches: Vec<&'a mut dyn PatchModifyable<Modifyable = AdvMapDwelling>>,
+    economy_info_provider: &'a EconomyInfoProvider,
+    rules: &'a Vec<EconomyRule>,
+    lua_table: LuaTable
+}
+
+impl<'a> DwellingPatchesGroup<'a> {
+    pub fn new(eip: &'a EconomyInfoProvider, rules: &'a Vec<EconomyRule>) -> Self {
+        DwellingPatchesGroup { 
+            patches: vec![], 
+            economy_info_provider: eip, 
+            rules: rules, 
+            lua_table: LuaTable::new()
+        }
+    }
+
+    pub fn with_modifyable(mut self, patch: &'a mut dyn PatchModifyable<Modifyable = AdvMapDwelling>) -> Self {
+        self.patches.push(patch);
+        self
+    }
+}
+
+impl<'a> PatchGroup for DwellingPatchesGroup<'a> {
+    fn run(&mut self, text: &String, writer: &mut quick_xml::Writer<&mut Vec<u8>>) {
+        let dwelling_de: Result<AdvMapDwelling, quick_xml::DeError> = quick_xml::de::from_str(&format!("<AdvMapDwelling>{}</AdvMapDwelling>", text));
+        match dwelling_de {
+            Ok(mut dwelling) => {
+                for patch in self.patches.iter_mut() {
+                    patch.try_modify(&mut dwelling);
+                }
+                let no_xpointer_shared = dwelling.shared.href.as_ref().unwrap().replace("#xpointer(/AdvMapDwellingShared)", "");
+                if let Some(info) = self.economy_info_provider.get_dwelling(&no_xpointer_shared) {
+                    let entrance = entrance_tile(dwelling.pos.x, dwelling.pos.y, dwelling.rot, &info.entrance);
+                    self.lua_table.insert(LuaKey::String(dwelling.name.clone()), LuaTable::new()
+                        .with_field("town", LuaValue::Constant(serde_json::to_value(&info.town).unwrap().as_str().unwrap().to_string()))
+                        .with_field("tier", info.tier)
+                        .with_field("creature", info.creature.as_ref().map_or(LuaValue::Nil, |c| LuaValue::Constant(c.clone())))
+                        .with_field("owner", player_to_lua(&dwelling.player_id))
+                        .with_field("x", entrance.0)
+                        .with_field("y", entrance.1)
+                        .with_field("production", production_multiplier(self.rules.iter().rev().filter(|r| r.is_for_dwelling(info))))
+                    );
+                }
+                writer.write_serializable("AdvMapDwelling", &dwelling).unwrap();
+            },
+            Err(e) => println!("Error deserializing dwelling: {}", e.to_string())
+        }
+    }
+}
+
+impl<'a> GenerateLuaCode for DwellingPatchesGroup<'a> {
+    fn to_lua(&self, path: &PathBuf) {
+        LuaChunk::new()
+            .with("BTD_Dwellings", self.lua_table.clone())
+            .write(path, "dwellings_info.lua");
+    }
+
+    fn generated_tables(&self) -> Vec<&'static str> {
+        match self.lua_table.is_empty() {
+            true => vec![],
+            false => vec!["BTD_Dwellings"]
+        }
+    }
+}
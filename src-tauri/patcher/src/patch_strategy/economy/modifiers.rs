@@ -0,0 +1,105 @@
+/// Modifyable patch strategies for mines and dwellings groups.
+
+use homm5_types::building::{AdvMapMine, AdvMapDwelling};
+use crate::patch_strategy::PatchModifyable;
+use super::{EconomyInfoProvider, EconomyRule};
+
+/// Applies script name to mine.
+pub struct MineNameApplier {
+    mines_count: u32
+}
+
+impl MineNameApplier {
+    pub fn new() -> Self {
+        MineNameApplier { 
+            mines_count: 0 
+        }
+    }
+}
+
+impl PatchModifyable for MineNameApplier {
+    type Modifyable = AdvMapMine;
+
+    fn try_modify(&mut self, object: &mut Self::Modifyable) {
+        self.mines_count += 1;
+        object.name = format!("btd_mine_{}", self.mines_count);
+    }
+}
+
+/// Applies script name to dwelling.
+pub struct DwellingNameApplier {
+    dwellings_count: u32
+}
+
+impl DwellingNameApplier {
+    pub fn new() -> Self {
+        DwellingNameApplier { 
+            dwellings_count: 0 
+        }
+    }
+}
+
+impl PatchModifyable for DwellingNameApplier {
+    type Modifyable = AdvMapDwelling;
+
+    fn try_modify(&mut self, object: &mut Self::Modifyable) {
+        self.dwellings_count += 1;
+        object.name = format!("btd_dwelling_{}", self.dwellings_count);
+    }
+}
+
+/// Changes owners of mines by rules, last matching rule wins.
+pub struct MineOwnerApplier<'a> {
+    economy_info_provider: &'a EconomyInfoProvider,
+    rules: &'a Vec<EconomyRule>
+}
+
+impl<'a> MineOwnerApplier<'a> {
+    pub fn new(eip: &'a EconomyInfoProvider, rules: &'a Vec<EconomyRule>) -> Self {
+        MineOwnerApplier { 
+            economy_info_provider: eip, 
+            rules: rules 
+        }
+    }
+}
+
+impl<'a> PatchModifyable for MineOwnerApplier<'a> {
+    type Modifyable = AdvMapMine;
+
+    fn try_modify(&mut self, object: &mut Self::Modifyable) {
+        let no_xpointer_shared = object.shared.href.as_ref().unwrap().replace("#xpointer(/AdvMapMineShared)", "");
+        if let Some(info) = self.economy_info_provider.get_mine(&no_xpointer_shared) {
+            if let Some(owner) = self.rules.iter().rev().filter(|r| r.is_for_mine(info)).find_map(|r| r.owner.clone()) {
+                object.player_id = owner;
+            }
+        }
+    }
+}
+
+/// Changes owners of dwellings by rules, last matching rule wins.
+pub struct DwellingOwnerApplier<'a> {
+    economy_info_provider: &'a EconomyInfoProvider,
+    rules: &'a Vec<EconomyRule>
+}
+
+impl<'a> DwellingOwnerApplier<'a> {
+    pub fn new(eip: &'a EconomyInfoProvider, rules: &'a Vec<EconomyRule>) -> Self {
+        DwellingOwnerApplier { 
+            economy_info_provider: eip, 
+            rules: rules 
+        }
+    }
+}
+
+impl<'a> PatchModifyable for DwellingOwnerApplier<'a> {
+    type Modifyable = AdvMapDwelling;
+
+    fn try_modify(&mut self, object: &mut Self::Modifyable) {
+        let no_xpointer_shared = object.shared.href.as_ref().unwrap().replace("#xpointer(/AdvMapDwellingShared)", "");
+        if let Some(info) = self.economy_info_provider.get_dwelling(&no_xpointer_shared) {
+            if let Some(owner) = self.rules.iter().rev().filter(|r| r.is_for_dwelling(info)).find_map(|r| r.owner.clone()) {
+                object.player_id = owner;
+            }
+        }
+    }
+}
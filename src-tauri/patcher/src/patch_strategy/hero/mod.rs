@@ -0,0 +1,150 @@
+pub mod modifiers;
+
+use std::{path::PathBuf, collections::HashMap};
+use homm5_types::{hero::AdvMapHero, player::PlayerID};
+use serde::{Serialize, Deserialize};
+use strum::IntoEnumIterator;
+use crate::lua::{LuaTable, LuaKey, LuaValue, LuaChunk};
+use super::{PatchModifyable, GenerateLuaCode, PatchGroup, player::HandicapCreature};
+
+/// Changes of pre-placed hero that are applied by map script(heroes_adjustments.json, adds/scripts/heroes.lua).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HeroAdjustment {
+    /// Creatures that replace hero's army, empty list means army isn't changed.
+    #[serde(default)]
+    pub army: Vec<HandicapCreature>,
+    /// Game constants of skills(SKILL_...) given to hero.
+    #[serde(default)]
+    pub skills: Vec<String>,
+    /// Game constants of spells(SPELL_...) taught to hero.
+    #[serde(default)]
+    pub spells: Vec<String>,
+    #[serde(default)]
+    pub experience: u32
+}
+
+impl HeroAdjustment {
+    /// Adds adjustment fields to hero's table.
+    fn to_lua(&self, hero_table: LuaTable) -> LuaTable {
+        let mut army = LuaTable::new();
+        for creature in self.army.iter() {
+            army.push(creature.to_lua());
+        }
+        let mut skills = LuaTable::new();
+        for skill in self.skills.iter() {
+            skills.push(LuaValue::Constant(skill.clone()));
+        }
+        let mut spells = LuaTable::new();
+        for spell in self.spells.iter() {
+            spells.push(LuaValue::Constant(spell.clone()));
+        }
+        hero_table
+            .with_field("army", army)
+            .with_field("skills", skills)
+            .with_field("spells", spells)
+            .with_field("experience", self.experience)
+    }
+}
+
+/// Provides information that can be used across different patches in HeroPatchesGroup.
+pub struct HeroInfoProvider {
+    /// maps heroes shareds to their adjustments.
+    adjustments: HashMap<String, HeroAdjustment>
+}
+
+impl HeroInfoProvider {
+    pub fn new(config: &PathBuf) -> Self {
+        // adjustments are optional, missing file means heroes aren't adjusted
+        let adjustments_de: Result<HashMap<String, HeroAdjustment>, String> = std::fs::read_to_string(config.join("heroes_adjustments.json"))
+            .map_err(|e| e.to_string())
+            .and_then(|adjustments_se| serde_json::from_str(&adjustments_se).map_err(|e| e.to_string()));
+        HeroInfoProvider { 
+            adjustments: adjustments_de.unwrap_or_else(|e| {
+                println!("Heroes adjustments aren't used: {}", &e);
+                HashMap::new()
+            })
+        }
+    }
+
+    /// Returns adjustment of hero based on its shared.
+    pub fn get_adjustment(&self, shared: &String) -> Option<&HeroAdjustment> {
+        self.adjustments.get(shared)
+    }
+}
+
+/// HeroPatchesGroup combines all necessary patches for AdvMapHero game type(heroes placed by map makers, prisons too).
+pub struct HeroPatchesGroup<'a> {
+    patches: Vec<&'a mut dyn PatchModifyable<Modifyable = AdvMapHero>>,
+    hero_info_provider: &'a HeroInfoProvider,
+    heroes_table: LuaTable,
+    is_removed: bool
+}
+
+impl<'a> HeroPatchesGroup<'a> {
+    pub fn new(hip: &'a HeroInfoProvider) -> Self {
+        HeroPatchesGroup { 
+            patches: vec![], 
+            hero_info_provider: hip,
+            heroes_table: LuaTable::new(),
+            is_removed: false
+        }
+    }
+
+    pub fn with_modifyable(mut self, patch: &'a mut dyn PatchModifyable<Modifyable = AdvMapHero>) -> Self {
+        self.patches.push(patch);
+        self
+    }
+}
+
+impl<'a> PatchGroup for HeroPatchesGroup<'a> {
+    fn run(&mut self, text: &String, writer: &mut quick_xml::Writer<&mut Vec<u8>>) {
+        let hero_de: Result<AdvMapHero, quick_xml::DeError> = quick_xml::de::from_str(&format!("<AdvMapHero>{}</AdvMapHero>", text));
+        match hero_de {
+            Ok(mut hero) => {
+                for patch in self.patches.iter_mut() {
+                    patch.try_modify(&mut hero);
+                    if patch.is_removal_requested() == true {
+                        self.is_removed = true;
+                        return;
+                    }
+                }
+                let no_xpointer_shared = hero.shared.href.as_ref().unwrap().replace("#xpointer(/AdvMapHeroShared)", "");
+                let owner = match PlayerID::iter().position(|p| p == hero.player_id) {
+                    Some(number) if number > 0 => format!("PLAYER_{}", number),
+                    _=> "PLAYER_NONE".to_string()
+                };
+                let mut hero_table = LuaTable::new()
+                    .with_field("owner", LuaValue::Constant(owner))
+                    .with_field("x", hero.pos.x)
+                    .with_field("y", hero.pos.y);
+                if let Some(adjustment) = self.hero_info_provider.get_adjustment(&no_xpointer_shared) {
+                    hero_table = adjustment.to_lua(hero_table);
+                }
+                self.heroes_table.insert(LuaKey::String(hero.name.clone()), hero_table);
+                writer.write_serializable("AdvMapHero", &hero).unwrap();
+            },
+            Err(e) => println!("Error deserializing hero: {}", e.to_string())
+        }
+    }
+
+    fn take_removal(&mut self) -> bool {
+        let is_removed = self.is_removed;
+        self.is_removed = false;
+        is_removed
+    }
+}
+
+impl<'a> GenerateLuaCode for HeroPatchesGroup<'a> {
+    fn to_lua(&self, path: &PathBuf) {
+        LuaChunk::new()
+            .with("BTD_Heroes", self.heroes_table.clone())
+            .write(path, "heroes_info.lua");
+    }
+
+    fn generated_tables(&self) -> Vec<&'static str> {
+        match self.heroes_table.is_empty() {
+            true => vec![],
+            false => vec!["BTD_Heroes"]
+        }
+    }
+}
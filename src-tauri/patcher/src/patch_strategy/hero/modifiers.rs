@@ -0,0 +1,91 @@
+/// Modifyable patch strategies for HeroPatchesGroup.
+
+use std::collections::HashMap;
+use homm5_types::{hero::AdvMapHero, player::PlayerID, common::FileRef};
+use strum::IntoEnumIterator;
+use crate::patch_strategy::{PatchModifyable, player::PlayerStartInfo};
+
+/// Applies script name to hero.
+pub struct HeroNameApplier {
+    heroes_count: u32
+}
+
+impl HeroNameApplier {
+    pub fn new() -> Self {
+        HeroNameApplier { 
+            heroes_count: 0 
+        }
+    }
+}
+
+impl PatchModifyable for HeroNameApplier {
+    type Modifyable = AdvMapHero;
+
+    fn try_modify(&mut self, object: &mut Self::Modifyable) {
+        self.heroes_count += 1;
+        object.name = format!("btd_hero_{}", self.heroes_count);
+    }
+}
+
+/// Removes pre-placed heroes nobody owns(free heroes and prisons) if some mode doesn't allow them(in Outcast they give players extra heroes).
+/// Heroes owned by players are kept, starting heroes are among them.
+pub struct MapHeroesRemover {
+    can_be_applied: bool,
+    is_current_removed: bool
+}
+
+impl MapHeroesRemover {
+    pub fn new(can_be_applied: bool) -> Self {
+        MapHeroesRemover { 
+            can_be_applied: can_be_applied,
+            is_current_removed: false
+        }
+    }
+}
+
+impl PatchModifyable for MapHeroesRemover {
+    type Modifyable = AdvMapHero;
+
+    fn try_modify(&mut self, object: &mut Self::Modifyable) {
+        self.is_current_removed = self.can_be_applied == true && object.player_id == PlayerID::PlayerNone;
+    }
+
+    fn is_removal_requested(&self) -> bool {
+        self.is_current_removed
+    }
+}
+
+/// Replaces player's first hero(generator places it near player's starting town) with starting hero forced for player.
+pub struct StartingHeroApplier<'a> {
+    start_info: &'a HashMap<usize, PlayerStartInfo>,
+    processed_players: Vec<PlayerID>
+}
+
+impl<'a> StartingHeroApplier<'a> {
+    pub fn new(start_info: &'a HashMap<usize, PlayerStartInfo>) -> Self {
+        StartingHeroApplier {
+            start_info: start_info,
+            processed_players: vec![]
+        }
+    }
+}
+
+impl<'a> PatchModifyable for StartingHeroApplier<'a> {
+    type Modifyable = AdvMapHero;
+
+    fn try_modify(&mut self, object: &mut Self::Modifyable) {
+        if object.player_id == PlayerID::PlayerNone || self.processed_players.contains(&object.player_id) {
+            return;
+        }
+        self.processed_players.push(object.player_id.clone());
+        let player_number = PlayerID::iter().position(|p| p == object.player_id).unwrap();
+        if let Some(hero) = self.start_info.get(&player_number).and_then(|i| i.hero.as_ref()) {
+            object.shared = FileRef {
+                href: Some(match hero.xdb.contains("#xpointer") {
+                    true => hero.xdb.clone(),
+                    false => format!("{}#xpointer(/AdvMapHeroShared)", &hero.xdb)
+                })
+            };
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use std::{path::PathBuf, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::{map::Map, lua::{LuaTable, LuaKey, LuaValue, LuaChunk}};
+use super::GenerateLuaCode;
+
+/// Writes btd_patch_info.lua that describes how map was produced:
+/// versions of launcher and patcher, settings, active modes, teams, seed, template and time of patch.
+/// Used by in-game scripts and to investigate problems of played maps.
+pub struct PatchInfoGenerator<'a> {
+    map: &'a Map,
+    launcher_version: &'a str
+}
+
+impl<'a> PatchInfoGenerator<'a> {
+    pub fn new(map: &'a Map, launcher_version: &'a str) -> Self {
+        PatchInfoGenerator {
+            map: map,
+            launcher_version: launcher_version
+        }
+    }
+}
+
+impl<'a> GenerateLuaCode for PatchInfoGenerator<'a> {
+    fn to_lua(&self, path: &PathBuf) {
+        // settings are written with their json names, so new settings are added here automatically
+        let mut settings = LuaTable::new();
+        if let serde_json::Value::Object(settings_values) = serde_json::to_value(&self.map.settings).unwrap() {
+            for setting in settings_values.iter() {
+                settings.insert(LuaKey::Name(setting.0.clone()), setting.1);
+            }
+        }
+        let mut mode_names = self.map.active_mode_names();
+        mode_names.sort();
+        let mut modes = LuaTable::new();
+        for mode in mode_names {
+            modes.push(mode);
+        }
+        // player's number is same as index of teams info, players without team aren't active
+        let mut teams = LuaTable::new();
+        for player in self.map.teams_info.iter().enumerate().skip(1) {
+            if *player.1 != 0 {
+                teams.insert(LuaKey::Constant(format!("PLAYER_{}", player.0)), *player.1);
+            }
+        }
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let template = match &self.map.template_name {
+            Some(name) => LuaValue::from(name),
+            None => LuaValue::Nil
+        };
+        let info = LuaTable::new()
+            .with_field("launcher_version", self.launcher_version)
+            .with_field("patcher_version", env!("CARGO_PKG_VERSION"))
+            .with_field("map", &self.map.name)
+            .with_field("template", template)
+            .with_field("seed", self.map.seed)
+            .with_field("timestamp", timestamp as i64)
+            .with_field("settings", settings)
+            .with_field("modes", modes)
+            .with_field("teams", teams);
+        LuaChunk::new()
+            .with("BTD_PatchInfo", info)
+            .write(path, "btd_patch_info.lua");
+    }
+
+    fn generated_tables(&self) -> Vec<&'static str> {
+        vec!["BTD_PatchInfo"]
+    }
+}
@@ -1,42 +1,154 @@
-use std::path::PathBuf;
-use rand::seq::IteratorRandom;
-use super::PatchCreatable;
+use std::{path::PathBuf, collections::HashMap};
+use homm5_types::town::{TownType, AdvMapTown};
+use rand::{SeedableRng, rngs::StdRng, distributions::{WeightedIndex, Distribution}};
+use serde::{Serialize, Deserialize};
+use crate::map::Map;
+use super::{PatchCreatable, town::TownInfoProvider};
 
 /// LightPatcher is a creatable patch strategy that adds lights to map and sets current light.
+/// Current light is picked with lights selection of map: user's pick is used if it is set and fits current time,
+/// otherwise light is selected by policy among lights of current time(day or night) that have all required tags.
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LightTime {
+    Day,
+    Night
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightInfo {
+    pub name: String,
+    pub path: String,
+    pub time: LightTime,
+    /// terrains this light fits.
+    #[serde(default)]
+    pub terrains: Vec<String>,
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    #[serde(default)]
+    pub tags: Vec<String>
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
 pub struct LightsModel {
-    pub day_lights: Vec<String>,
-    pub night_lights: Vec<String>
+    pub lights: Vec<LightInfo>,
+    /// names of lights used by fixed policy.
+    pub fixed: HashMap<LightTime, String>,
+    /// native terrains of towns, map's dominant terrain is the most common native terrain of its towns.
+    pub town_terrains: HashMap<TownType, String>
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LightSelectionPolicy {
+    Fixed,
+    #[default]
+    WeightedRandom,
+    /// weighted random among lights that fit dominant terrain of map, all lights are used if none fits.
+    DominantTerrain
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LightsSelection {
+    pub policy: LightSelectionPolicy,
+    /// name of light user picked.
+    pub picked: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>
 }
 
 pub struct LightsInfoProvider {
     current_lights: Vec<String>,
-    current_light: String,
+    current_light: Option<LightInfo>,
 }
 
 impl LightsInfoProvider {
-    pub fn new(config: &PathBuf, use_night_lights: bool) -> Self {
-        let lights_de: LightsModel = serde_json::from_str(
-            &std::fs::read_to_string(config.join("lights.json")).unwrap()
-        ).unwrap();
-        let mut rng = rand::thread_rng();
-        match use_night_lights {
-            true => {
-                let current_light = lights_de.night_lights.iter().choose(&mut rng).unwrap();
-                LightsInfoProvider {
-                    current_lights: lights_de.night_lights.clone(),
-                    current_light: current_light.clone()
-                }
+    pub fn new(config: &PathBuf, map: &Map, town_info_provider: &TownInfoProvider) -> Self {
+        // lights are edited by hand often, so errors must not break whole patching process.
+        let lights_de: LightsModel = std::fs::read_to_string(config.join("lights.json"))
+            .map_err(|e| e.to_string())
+            .and_then(|lights_se| serde_json::from_str(&lights_se).map_err(|e| e.to_string()))
+            .unwrap_or_else(|e| {
+                println!("Error reading lights: {}", &e);
+                LightsModel::default()
+            });
+        let time = match map.settings.use_night_lights {
+            true => LightTime::Night,
+            false => LightTime::Day
+        };
+        // map must always have ambient light, so the first light of current time is used if none can be selected
+        let current_light = Self::select(&lights_de, map, town_info_provider, time).or_else(|| {
+            println!("No light can be selected for map with selection {:?}, first light of {:?} is used", &map.lights_selection, time);
+            lights_de.lights.iter().find(|l| l.time == time).cloned()
+        });
+        LightsInfoProvider {
+            current_lights: lights_de.lights.iter().filter(|l| l.time == time).map(|l| l.path.clone()).collect(),
+            current_light: current_light
+        }
+    }
+
+    fn select(model: &LightsModel, map: &Map, town_info_provider: &TownInfoProvider, time: LightTime) -> Option<LightInfo> {
+        let selection = &map.lights_selection;
+        if let Some(picked) = &selection.picked {
+            match model.lights.iter().find(|l| l.name == *picked && l.time == time) {
+                Some(light) => return Some(light.clone()),
+                None => println!("Picked light {} isn't found among lights of {:?}, light is selected by policy", picked, time)
+            }
+        }
+        let candidates: Vec<&LightInfo> = model.lights.iter()
+            .filter(|l| l.time == time && selection.tags.iter().all(|t| l.tags.contains(t)))
+            .collect();
+        let candidates = match selection.policy {
+            LightSelectionPolicy::Fixed => {
+                return model.fixed.get(&time)
+                    .and_then(|name| candidates.iter().find(|l| l.name == *name))
+                    .map(|l| (*l).clone());
             },
-            false => {
-                let current_light = lights_de.day_lights.iter().choose(&mut rng).unwrap();
-                LightsInfoProvider {
-                    current_lights: lights_de.day_lights.clone(),
-                    current_light: current_light.clone()
+            LightSelectionPolicy::WeightedRandom => candidates,
+            LightSelectionPolicy::DominantTerrain => {
+                match Self::dominant_terrain(model, map, town_info_provider) {
+                    Some(terrain) if candidates.iter().any(|l| l.terrains.contains(&terrain)) => {
+                        candidates.into_iter().filter(|l| l.terrains.contains(&terrain)).collect()
+                    },
+                    _=> candidates
                 }
             }
+        };
+        // same seed gives same light, so preview matches patched map
+        let mut rng = StdRng::seed_from_u64(map.seed as u64);
+        WeightedIndex::new(candidates.iter().map(|l| l.weight))
+            .ok()
+            .map(|distribution| candidates[distribution.sample(&mut rng)].clone())
+    }
+
+    /// Returns the most common native terrain of towns placed in map.
+    fn dominant_terrain(model: &LightsModel, map: &Map, town_info_provider: &TownInfoProvider) -> Option<String> {
+        let towns = match map.read_objects::<AdvMapTown>("AdvMapTown") {
+            Ok(towns) => towns,
+            Err(e) => {
+                println!("Error reading towns to detect dominant terrain: {}", &e);
+                return None;
+            }
+        };
+        let mut terrains: HashMap<&String, u32> = HashMap::new();
+        for town in towns.iter() {
+            let no_xpointer_shared = town.shared.href.as_ref().unwrap().replace("#xpointer(/AdvMapTownShared)", "");
+            if let Some(terrain) = town_info_provider.get_town_type(&no_xpointer_shared).and_then(|t| model.town_terrains.get(t)) {
+                *terrains.entry(terrain).or_insert(0) += 1;
+            }
         }
+        // sorted, so ties don't depend on order of config
+        let mut terrains: Vec<(&String, u32)> = terrains.into_iter().collect();
+        terrains.sort_by(|first, second| second.1.cmp(&first.1).then(first.0.cmp(second.0)));
+        terrains.first().map(|t| t.0.clone())
+    }
+
+    /// Returns name of light that will be set for map.
+    pub fn current_light_name(&self) -> Option<&String> {
+        self.current_light.as_ref().map(|l| &l.name)
     }
 }
 
@@ -46,7 +158,7 @@ pub struct AmbientLightCreator<'a> {
 
 impl<'a> AmbientLightCreator<'a> {
     pub fn new(lip: &'a LightsInfoProvider) -> Self {
-        AmbientLightCreator { 
+        AmbientLightCreator {
             lights_info_provider: lip
         }
     }
@@ -54,9 +166,11 @@ impl<'a> AmbientLightCreator<'a> {
 
 impl<'a> PatchCreatable for AmbientLightCreator<'a>  {
     fn try_create(&self, writer: &mut quick_xml::Writer<&mut Vec<u8>>) {
-        writer.create_element("AmbientLight")
-            .with_attribute(("href", self.lights_info_provider.current_light.as_str()))
-            .write_empty().unwrap();
+        if let Some(light) = &self.lights_info_provider.current_light {
+            writer.create_element("AmbientLight")
+                .with_attribute(("href", light.path.as_str()))
+                .write_empty().unwrap();
+        }
     }
 }
 
@@ -74,15 +188,15 @@ impl<'a> GroundAmbientLightsCreator<'a>  {
 
 impl<'a> PatchCreatable for GroundAmbientLightsCreator<'a> {
     fn try_create(&self, writer: &mut quick_xml::Writer<&mut Vec<u8>>) {
-        writer.create_element("GroundAmbientLigts").
+        writer.create_element("GroundAmbientLights").
             write_inner_content(|w| {
                 for light in self.lights_info_provider.current_lights.iter() {
                     w.create_element("Item")
                         .with_attribute(("href", light.as_str()))
-                        .write_empty().unwrap(); 
+                        .write_empty().unwrap();
                 }
                 Ok(())
             }
         ).unwrap();
     }
-}
\ No newline at end of file
+}
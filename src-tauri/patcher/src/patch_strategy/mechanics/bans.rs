@@ -0,0 +1,287 @@
+/// Spells and skills bans of map.
+
+use std::{path::PathBuf, io::Write, sync::RwLock, collections::HashMap};
+use serde::{Serialize, Deserialize};
+use crate::{
+    map::Map,
+    lua::{LuaTable, LuaKey, LuaChunk},
+    report::PatchReport,
+    patch_strategy::{WriteAdditional, GenerateLuaCode, artifact::table::{tag_value, table_items}}
+};
+
+/// Bans are applied to map's GameMechanics files based on game's ones(they are extracted from game data while scanning files into adds/game/).
+/// Banned skills get zero probabilities in RefTables/HeroClass.xdb, banned spells get ban fields from adds/bans/bans.json,
+/// modified spells get their own fields. Banned spells and skills are also written into lua tables(BTD_BannedSpells[SPELL_...] == true),
+/// so scripts can check them.
+
+/// Changes of fields of spell's xdb.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpellOverride {
+    /// Game constant of spell(SPELL_...).
+    pub spell: String,
+    /// new values of fields by their tags.
+    pub fields: HashMap<String, String>
+}
+
+/// Bans of map. Preset bans are added to map's own ones.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MechanicsBans {
+    /// name of preset from adds/bans/bans.json.
+    pub preset: Option<String>,
+    /// Game constants of banned spells.
+    #[serde(default)]
+    pub spells: Vec<String>,
+    /// Game constants of banned skills(SKILL_...).
+    #[serde(default)]
+    pub skills: Vec<String>,
+    #[serde(default)]
+    pub overrides: Vec<SpellOverride>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BansPreset {
+    pub name: String,
+    #[serde(default)]
+    pub spells: Vec<String>,
+    #[serde(default)]
+    pub skills: Vec<String>,
+    #[serde(default)]
+    pub overrides: Vec<SpellOverride>
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BansConfig {
+    /// fields that are set for every banned spell.
+    spell_ban_fields: HashMap<String, String>,
+    presets: Vec<BansPreset>
+}
+
+/// Spell of game's spells table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaseSpell {
+    pub id: String,
+    /// path of spell's xdb in GameMechanics/.
+    pub file: String
+}
+
+/// Returns spells of game's spells table with their files. Game files scanning stores them into adds/game/spells.json
+/// together with spells scanned for map scripts, so patcher uses this list.
+pub fn read_spells(table: &str) -> Vec<BaseSpell> {
+    table_items(table).into_iter()
+        .filter_map(|(start, end)| {
+            let item = &table[start..end];
+            let href_start = item.find("href=\"")? + "href=\"".len();
+            let href_end = item[href_start..].find(|c: char| c == '"' || c == '#')? + href_start;
+            let file = item[href_start..href_end].trim_start_matches('/');
+            // hrefs are absolute paths of game's files
+            let file = file.strip_prefix("GameMechanics/").unwrap_or(file);
+            tag_value(item, "ID").map(|id| BaseSpell { id: id.to_string(), file: file.replace("/", "\\") })
+        })
+        .filter(|s| s.file.is_empty() == false)
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+struct BansRecord<'a> {
+    spells: &'a Vec<String>,
+    skills: &'a Vec<String>,
+    overridden_spells: Vec<&'a String>
+}
+
+/// Provides game's spells and bans presets.
+pub struct MechanicsBansProvider {
+    game_dir: PathBuf,
+    config: BansConfig,
+    spells: Vec<BaseSpell>
+}
+
+impl MechanicsBansProvider {
+    pub fn new(config: &PathBuf) -> Self {
+        let game_dir = config.join("adds\\game\\GameMechanics\\");
+        let config_de: Result<BansConfig, String> = std::fs::read_to_string(config.join("adds\\bans\\bans.json"))
+            .map_err(|e| e.to_string())
+            .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()));
+        let spells_de: Result<Vec<BaseSpell>, String> = std::fs::read_to_string(config.join("adds\\game\\spells.json"))
+            .map_err(|e| e.to_string())
+            .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()));
+        let spells = spells_de.unwrap_or_else(|e| {
+            println!("Error reading scanned spells(game files must be scanned to get them): {}", e);
+            vec![]
+        });
+        MechanicsBansProvider {
+            game_dir: game_dir,
+            config: config_de.unwrap_or_else(|e| {
+                println!("Error reading bans config: {}", e);
+                BansConfig::default()
+            }),
+            spells: spells
+        }
+    }
+
+    pub fn get_spells(&self) -> &Vec<BaseSpell> {
+        &self.spells
+    }
+
+    pub fn get_presets(&self) -> &Vec<BansPreset> {
+        &self.config.presets
+    }
+
+    /// Returns map's bans with bans of its preset.
+    pub fn resolve(&self, bans: &MechanicsBans) -> MechanicsBans {
+        let mut resolved = bans.clone();
+        if let Some(preset_name) = &bans.preset {
+            match self.config.presets.iter().find(|p| p.name == *preset_name) {
+                Some(preset) => {
+                    resolved.spells.extend(preset.spells.iter().filter(|s| bans.spells.contains(s) == false).cloned());
+                    resolved.skills.extend(preset.skills.iter().filter(|s| bans.skills.contains(s) == false).cloned());
+                    // map's own overrides are more important than preset ones
+                    resolved.overrides.extend(preset.overrides.iter().filter(|o| bans.overrides.iter().all(|m| m.spell != o.spell)).cloned());
+                },
+                None => println!("Bans preset {} isn't found", preset_name)
+            }
+        }
+        resolved
+    }
+}
+
+/// Writes spells and skills changes into map's GameMechanics/ and generates lua tables of bans.
+pub struct MechanicsBansGenerator<'a> {
+    provider: &'a MechanicsBansProvider,
+    bans: MechanicsBans,
+    write_dir: &'a PathBuf
+}
+
+impl<'a> MechanicsBansGenerator<'a> {
+    pub fn new(provider: &'a MechanicsBansProvider, map: &'a Map, report: &RwLock<PatchReport>) -> Self {
+        let bans = provider.resolve(&map.mechanics_bans);
+        report.write().unwrap().add("mechanics_bans", &BansRecord {
+            spells: &bans.spells,
+            skills: &bans.skills,
+            overridden_spells: bans.overrides.iter().map(|o| &o.spell).collect()
+        });
+        MechanicsBansGenerator {
+            provider: provider,
+            bans: bans,
+            write_dir: &map.game_mechanics_dir
+        }
+    }
+
+    /// Fields of spells that must be changed.
+    fn spells_fields(&self) -> HashMap<&String, HashMap<&String, &String>> {
+        let mut fields: HashMap<&String, HashMap<&String, &String>> = HashMap::new();
+        for spell in self.bans.spells.iter() {
+            fields.entry(spell).or_insert(HashMap::new()).extend(self.provider.config.spell_ban_fields.iter());
+        }
+        for spell_override in self.bans.overrides.iter() {
+            fields.entry(&spell_override.spell).or_insert(HashMap::new()).extend(spell_override.fields.iter());
+        }
+        fields
+    }
+
+    fn write_spells(&self) {
+        for (spell, fields) in self.spells_fields() {
+            let base_spell = match self.provider.spells.iter().find(|s| s.id == *spell) {
+                Some(base_spell) => base_spell,
+                None => {
+                    println!("Spell {} isn't found in game's spells table", spell);
+                    continue;
+                }
+            };
+            // spell can be already written into map by other patch(outcast mechanics for example), then it is changed instead of game's one
+            let path_to = self.write_dir.join(&base_spell.file);
+            let spell_text = std::fs::read_to_string(&path_to)
+                .or_else(|_e| std::fs::read_to_string(self.provider.game_dir.join(&base_spell.file)));
+            let mut spell_text = match spell_text {
+                Ok(text) => text,
+                Err(e) => {
+                    println!("Error reading file {} of spell {}: {}", &base_spell.file, spell, e.to_string());
+                    continue;
+                }
+            };
+            for (tag, value) in fields {
+                match set_tag_value(&spell_text, tag, value) {
+                    Some(text) => spell_text = text,
+                    None => println!("Spell {} has no field {}", spell, tag)
+                }
+            }
+            std::fs::create_dir_all(&path_to.parent().unwrap()).unwrap();
+            let mut file = std::fs::File::create(&path_to).unwrap();
+            file.write_all(spell_text.as_bytes()).unwrap();
+        }
+    }
+
+    fn write_skills(&self) {
+        if self.bans.skills.is_empty() {
+            return;
+        }
+        let classes_path = PathBuf::from("RefTables\\HeroClass.xdb");
+        let mut classes_text = match std::fs::read_to_string(self.provider.game_dir.join(&classes_path)) {
+            Ok(text) => text,
+            Err(e) => {
+                println!("Error reading base hero classes table: {}", e.to_string());
+                return;
+            }
+        };
+        // every class has probabilities of skills, banned skills must never be offered
+        for skill in self.bans.skills.iter() {
+            let skill_tag = format!("<SkillID>{}</SkillID>", skill);
+            let mut position = 0;
+            while let Some(skill_start) = classes_text[position..].find(&skill_tag).map(|s| s + position) {
+                let prob_start = skill_start + skill_tag.len();
+                match set_tag_value(&classes_text[prob_start..], "Prob", "0") {
+                    Some(text) => classes_text = format!("{}{}", &classes_text[..prob_start], text),
+                    None => println!("Skill {} has no probability in hero classes table", skill)
+                }
+                position = prob_start;
+            }
+        }
+        let path_to = self.write_dir.join(&classes_path);
+        std::fs::create_dir_all(&path_to.parent().unwrap()).unwrap();
+        let mut file = std::fs::File::create(&path_to).unwrap();
+        file.write_all(classes_text.as_bytes()).unwrap();
+    }
+}
+
+/// Replaces value of first field with given tag, returns None if there is no such field.
+fn set_tag_value(text: &str, tag: &str, value: &str) -> Option<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = text.find(&open)? + open.len();
+    let end = text[start..].find(&close)? + start;
+    Some(format!("{}{}{}", &text[..start], value, &text[end..]))
+}
+
+impl<'a> WriteAdditional for MechanicsBansGenerator<'a> {
+    fn try_write(&self) {
+        self.write_spells();
+        self.write_skills();
+    }
+}
+
+impl<'a> GenerateLuaCode for MechanicsBansGenerator<'a> {
+    fn to_lua(&self, path: &PathBuf) {
+        let mut spells_table = LuaTable::new();
+        for spell in self.bans.spells.iter() {
+            spells_table.insert(LuaKey::Constant(spell.clone()), true);
+        }
+        let mut skills_table = LuaTable::new();
+        for skill in self.bans.skills.iter() {
+            skills_table.insert(LuaKey::Constant(skill.clone()), true);
+        }
+        LuaChunk::new()
+            .with("BTD_BannedSpells", spells_table)
+            .with("BTD_BannedSkills", skills_table)
+            .write(path, "bans_info.lua");
+    }
+
+    fn generated_tables(&self) -> Vec<&'static str> {
+        let mut tables = vec![];
+        if self.bans.spells.is_empty() == false {
+            tables.push("BTD_BannedSpells");
+        }
+        if self.bans.skills.is_empty() == false {
+            tables.push("BTD_BannedSkills");
+        }
+        tables
+    }
+}
@@ -1,19 +1,25 @@
 /// GameMechanics changes.
 
-use std::path::PathBuf;
+pub mod moon_calendar;
+pub mod bans;
+
+use std::{path::PathBuf, io::Write};
 use super::WriteAdditional;
 
 /// MoonCalendarWriter is a file add strategy that puts modified moon calendar into map is such setting is chosen by player.
+/// Calendar generated from spec(see moon_calendar) is preferred to prebuilt neutral weeks one.
 pub struct MoonCalendarWriter<'a> {
     neutral_weeks_only: bool,
+    generated_calendar: Option<String>,
     write_dir: &'a PathBuf,
     file_path: &'a PathBuf
 }
 
 impl<'a> MoonCalendarWriter<'a> {
-    pub fn new(neutral_weeks_setting: bool, dir: &'a PathBuf, path: &'a PathBuf) -> Self {
+    pub fn new(neutral_weeks_setting: bool, generated: Option<String>, dir: &'a PathBuf, path: &'a PathBuf) -> Self {
         MoonCalendarWriter { 
             neutral_weeks_only: neutral_weeks_setting, 
+            generated_calendar: generated,
             write_dir: dir, 
             file_path: path 
         }
@@ -22,40 +28,13 @@ impl<'a> MoonCalendarWriter<'a> {
 
 impl<'a> WriteAdditional for MoonCalendarWriter<'a> {
     fn try_write(&self) {
-        if self.neutral_weeks_only == true {
-            let path_to = self.write_dir.join("MoonCalendar\\Default.xdb");
+        let path_to = self.write_dir.join("MoonCalendar\\Default.xdb");
+        if let Some(calendar) = &self.generated_calendar {
             std::fs::create_dir_all(&path_to.parent().unwrap()).unwrap();
-            let copy_result = std::fs::copy(&self.file_path, &path_to);
-            match copy_result {
-                Ok(_num) => {},
-                Err(_e) => {
-                    println!("error copying file from {:?} to {:?}", &self.file_path, &path_to);
-                }
-            }
+            let mut file = std::fs::File::create(&path_to).unwrap();
+            file.write_all(calendar.as_bytes()).unwrap();
         }
-    }
-}
-
-pub struct NewArtifactsEnabler<'a> {
-    use_new_arts: bool,
-    write_dir: &'a PathBuf,
-    file_path: &'a PathBuf
-}
-
-impl<'a> NewArtifactsEnabler<'a> {
-    pub fn new(use_new_arts_setting: bool, dir: &'a PathBuf, path: &'a PathBuf) -> Self {
-        NewArtifactsEnabler { 
-            use_new_arts: use_new_arts_setting, 
-            write_dir: dir, 
-            file_path: path 
-        }
-    }
-}
-
-impl<'a> WriteAdditional for NewArtifactsEnabler<'a> {
-    fn try_write(&self) {
-        if self.use_new_arts == true {
-            let path_to = self.write_dir.join("RefTables\\Artifacts.xdb");
+        else if self.neutral_weeks_only == true {
             std::fs::create_dir_all(&path_to.parent().unwrap()).unwrap();
             let copy_result = std::fs::copy(&self.file_path, &path_to);
             match copy_result {
@@ -66,4 +45,4 @@ impl<'a> WriteAdditional for NewArtifactsEnabler<'a> {
             }
         }
     }
-}
\ No newline at end of file
+}
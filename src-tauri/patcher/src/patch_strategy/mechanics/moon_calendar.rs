@@ -0,0 +1,235 @@
+use std::{collections::HashMap, path::PathBuf, sync::RwLock};
+use rand::{SeedableRng, rngs::StdRng, distributions::{WeightedIndex, Distribution}};
+use serde::{Serialize, Deserialize};
+
+use crate::{map::{Map, template::{TemplateModeName, TemplateModeType}}, report::PatchReport};
+
+/// Moon calendar of map is generated from spec: every week of calendar gets a type, forced weeks are placed first,
+/// others are picked by weights with random sequence seeded by map's seed, so the same spec and seed give the same calendar.
+/// Format of Default.xdb is taken from templates in adds/moon_calendar/:
+/// calendar.xdb has {weeks} placeholder, week.xdb is repeated for every week with {number}, {month}, {week} and {id} placeholders.
+
+/// Weeks in month of game calendar.
+const WEEKS_IN_MONTH: u8 = 4;
+const DEFAULT_MONTHS: u8 = 12;
+
+/// Week of calendar some type is forced at.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum WeekDate {
+    Fixed {
+        month: u8,
+        week: u8
+    },
+    /// Week before the week of final battle, if this mode is active.
+    BeforeFinalBattle
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForcedWeek {
+    pub date: WeekDate,
+    /// game id of week type.
+    pub week: String
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MoonCalendarSpec {
+    /// weights of week types, types not listed here have default weights from weeks.json.
+    #[serde(default)]
+    pub weights: HashMap<String, u32>,
+    /// week types that can't be picked randomly(they still can be forced).
+    #[serde(default)]
+    pub banned: Vec<String>,
+    #[serde(default)]
+    pub forced: Vec<ForcedWeek>,
+    /// length of calendar in months, 12 if not set.
+    pub months: Option<u8>
+}
+
+/// Provides week types and templates of calendar.
+pub struct MoonCalendarInfoProvider {
+    /// default weights of week types by their ids.
+    weeks: HashMap<String, u32>,
+    calendar_template: Option<String>,
+    week_template: Option<String>
+}
+
+impl MoonCalendarInfoProvider {
+    pub fn new(config: &PathBuf) -> Self {
+        let calendar_dir = config.join("adds\\moon_calendar\\");
+        let weeks_de: Result<HashMap<String, u32>, String> = std::fs::read_to_string(calendar_dir.join("weeks.json"))
+            .map_err(|e| e.to_string())
+            .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()));
+        MoonCalendarInfoProvider {
+            weeks: weeks_de.unwrap_or_else(|e| {
+                println!("Error reading moon calendar weeks: {}", e);
+                HashMap::new()
+            }),
+            calendar_template: std::fs::read_to_string(calendar_dir.join("calendar.xdb")).ok(),
+            week_template: std::fs::read_to_string(calendar_dir.join("week.xdb")).ok()
+        }
+    }
+
+    /// Returns ids of week types for every week of calendar.
+    pub fn generate(&self, spec: &MoonCalendarSpec, map: &Map) -> Result<Vec<String>, String> {
+        let weeks_count = spec.months.unwrap_or(DEFAULT_MONTHS) as usize * WEEKS_IN_MONTH as usize;
+        let mut calendar: Vec<Option<String>> = vec![None; weeks_count];
+        for forced in spec.forced.iter() {
+            let (month, week) = match &forced.date {
+                WeekDate::Fixed { month, week } => (*month, *week),
+                WeekDate::BeforeFinalBattle => {
+                    match map.get_mode(&TemplateModeName::FinalBattle) {
+                        Some(TemplateModeType::FinalBattle(time)) if time.month > 1 || time.week > 1 => {
+                            match time.week {
+                                1 => (time.month - 1, WEEKS_IN_MONTH),
+                                _=> (time.month, time.week - 1)
+                            }
+                        },
+                        Some(TemplateModeType::FinalBattle(_)) => return Err("Final battle is in first week, no week before it".to_string()),
+                        _=> return Err(format!("Week {} is forced before final battle, but this mode isn't active", &forced.week))
+                    }
+                }
+            };
+            if month == 0 || week == 0 || week > WEEKS_IN_MONTH {
+                return Err(format!("Incorrect date of forced week {}: month {}, week {}", &forced.week, month, week));
+            }
+            let index = (month - 1) as usize * WEEKS_IN_MONTH as usize + (week - 1) as usize;
+            match calendar.get_mut(index) {
+                Some(Some(other)) if *other != forced.week => {
+                    return Err(format!("Weeks {} and {} are forced at the same date", other, &forced.week));
+                },
+                Some(entry) => *entry = Some(forced.week.clone()),
+                None => return Err(format!("Forced week {} is out of calendar", &forced.week))
+            }
+        }
+        // sorted, so sequence doesn't depend on order of config
+        let mut candidates: Vec<(&String, u32)> = self.weeks.iter()
+            .filter(|w| spec.banned.contains(w.0) == false)
+            .map(|w| (w.0, *spec.weights.get(w.0).unwrap_or(w.1)))
+            .filter(|w| w.1 > 0)
+            .collect();
+        candidates.sort_by(|first, second| first.0.cmp(second.0));
+        let mut rng = StdRng::seed_from_u64(map.seed as u64);
+        let distribution = WeightedIndex::new(candidates.iter().map(|c| c.1));
+        calendar.into_iter()
+            .map(|week| {
+                match (week, &distribution) {
+                    (Some(forced), _) => Ok(forced),
+                    (None, Ok(distribution)) => Ok(candidates[distribution.sample(&mut rng)].0.clone()),
+                    (None, Err(_)) => Err("No week types can be picked randomly".to_string())
+                }
+            })
+            .collect()
+    }
+
+    /// Creates Default.xdb content for given weeks.
+    pub fn to_xdb(&self, weeks: &Vec<String>) -> Result<String, String> {
+        match (&self.calendar_template, &self.week_template) {
+            (Some(calendar), Some(week_template)) => {
+                let mut weeks_xdb = String::new();
+                for week in weeks.iter().enumerate() {
+                    weeks_xdb += &week_template
+                        .replace("{number}", &(week.0 + 1).to_string())
+                        .replace("{month}", &(week.0 / WEEKS_IN_MONTH as usize + 1).to_string())
+                        .replace("{week}", &(week.0 % WEEKS_IN_MONTH as usize + 1).to_string())
+                        .replace("{id}", &quick_xml::escape::escape(week.1));
+                }
+                Ok(calendar.replace("{weeks}", &weeks_xdb))
+            },
+            _=> Err("Templates of moon calendar are missing".to_string())
+        }
+    }
+
+    /// Generates calendar of map and records its weeks into patch report. Returns None if calendar can't be created.
+    pub fn create(&self, spec: &MoonCalendarSpec, map: &Map, report: &RwLock<PatchReport>) -> Option<String> {
+        let xdb = self.generate(spec, map)
+            .and_then(|weeks| {
+                report.write().unwrap().add("moon_calendar", &weeks);
+                self.to_xdb(&weeks)
+            });
+        match xdb {
+            Ok(xdb) => Some(xdb),
+            Err(e) => {
+                println!("Error creating moon calendar: {}", e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::{map::{Map, template::{TemplateModeName, TemplateModeType}}, patch_strategy::modes::final_battle::FinalBattleTime};
+    use super::{MoonCalendarInfoProvider, MoonCalendarSpec, ForcedWeek, WeekDate};
+
+    fn provider() -> MoonCalendarInfoProvider {
+        MoonCalendarInfoProvider {
+            weeks: HashMap::from([
+                ("WEEK_NEUTRAL".to_string(), 10),
+                ("WEEK_OF_FIRE".to_string(), 5),
+                ("WEEK_OF_PLAGUE".to_string(), 1)
+            ]),
+            calendar_template: None,
+            week_template: None
+        }
+    }
+
+    fn map_with_final_battle(month: u8, week: u8) -> Map {
+        let mut map = Map::new();
+        map.seed = 42;
+        map.add_mode(TemplateModeName::FinalBattle, TemplateModeType::FinalBattle(FinalBattleTime { month: month, week: week, day: 1 }));
+        map
+    }
+
+    #[test]
+    fn forced_weeks_are_placed_at_their_dates() {
+        let spec = MoonCalendarSpec {
+            banned: vec!["WEEK_OF_PLAGUE".to_string()],
+            forced: vec![ForcedWeek { date: WeekDate::Fixed { month: 2, week: 3 }, week: "WEEK_OF_PLAGUE".to_string() }],
+            months: Some(3),
+            ..Default::default()
+        };
+        let mut map = Map::new();
+        map.seed = 42;
+        let calendar = provider().generate(&spec, &map).unwrap();
+        assert_eq!(calendar.len(), 12);
+        assert_eq!(calendar[6], "WEEK_OF_PLAGUE");
+        // banned week can only be forced
+        assert_eq!(calendar.iter().filter(|w| *w == "WEEK_OF_PLAGUE").count(), 1);
+    }
+
+    #[test]
+    fn week_before_final_battle_is_forced() {
+        let spec = MoonCalendarSpec {
+            forced: vec![ForcedWeek { date: WeekDate::BeforeFinalBattle, week: "WEEK_OF_PLAGUE".to_string() }],
+            weights: HashMap::from([("WEEK_OF_PLAGUE".to_string(), 0)]),
+            ..Default::default()
+        };
+        // final battle in first week of month is preceded by last week of previous month
+        let calendar = provider().generate(&spec, &map_with_final_battle(3, 1)).unwrap();
+        assert_eq!(calendar[7], "WEEK_OF_PLAGUE");
+        assert_eq!(calendar.iter().filter(|w| *w == "WEEK_OF_PLAGUE").count(), 1);
+    }
+
+    #[test]
+    fn final_battle_in_first_week_has_no_week_before() {
+        let spec = MoonCalendarSpec {
+            forced: vec![ForcedWeek { date: WeekDate::BeforeFinalBattle, week: "WEEK_OF_PLAGUE".to_string() }],
+            ..Default::default()
+        };
+        assert!(provider().generate(&spec, &map_with_final_battle(1, 1)).is_err());
+        // mode isn't active at all
+        assert!(provider().generate(&spec, &Map::new()).is_err());
+    }
+
+    #[test]
+    fn same_seed_gives_same_calendar() {
+        let spec = MoonCalendarSpec::default();
+        let mut map = Map::new();
+        map.seed = 7;
+        let first = provider().generate(&spec, &map).unwrap();
+        assert_eq!(first, provider().generate(&spec, &map).unwrap());
+        map.seed = 8;
+        assert_ne!(first, provider().generate(&spec, &map).unwrap());
+    }
+}
@@ -12,6 +12,12 @@ pub mod objects;
 pub mod mechanics;
 pub mod modes;
 pub mod terrain;
+pub mod substitution;
+pub mod artifact;
+pub mod economy;
+pub mod hero;
+pub mod info;
+pub mod script;
 
 /// This mod presents all types of possible patch strategies that can be applied to map files.
 
@@ -21,6 +27,11 @@ pub trait PatchModifyable {
     /// text: text parsed from xml document
     /// writer: quick-xml Writer to write modified elements into
     fn try_modify(&mut self, object: &mut Self::Modifyable);
+
+    /// Returns true if last modified object must be removed from map.
+    fn is_removal_requested(&self) -> bool {
+        false
+    }
 }
 
 pub trait PatchAdditional {
@@ -43,6 +54,11 @@ pub trait GenerateLuaCode {
     /// Generates lua code from insides of implementor
     /// path: map directory to put lua file(s) into
     fn to_lua(&self, path: &PathBuf);
+
+    /// Names of not empty tables implementor generates, used to check script modules that handle them.
+    fn generated_tables(&self) -> Vec<&'static str> {
+        vec![]
+    }
 }
 
 pub trait WriteAdditional {
@@ -59,6 +75,12 @@ pub trait PatchGroup {
     //fn get_patchable_object(&self, text: &String);
     fn run(&mut self, text: &String, writer: &mut quick_xml::Writer<&mut Vec<u8>>);
 
+    /// Returns true if last processed object was removed(and wasn't written), so Patcher must remove its parent tag as well.
+    /// Flag is reset after check.
+    fn take_removal(&mut self) -> bool {
+        false
+    }
+
     // fn with_modifyable(&mut self, patch: &dyn PatchModifyable<Modifyable = Self::Patchable>);
 
     // fn with_getter(&mut self, patch: &dyn PatchGetter<Patchable = Self::Patchable, Additional = Self::Additional>);
@@ -0,0 +1,227 @@
+use std::{collections::HashMap, path::PathBuf};
+use serde::{Serialize, Deserialize};
+use crate::{map::Map, lua::{LuaValue, LuaTable}};
+
+/// Data-driven descriptions of game modes.
+/// Every file in cfg/patcher/modes/ describes one mode: its parameters, the way it is written into modes_info.lua,
+/// its quest text files, placeholders of these texts and patch strategies it turns on.
+/// Built-in modes are described the same way, so new modes can be shipped with updater as config + lua only.
+
+/// Patch strategies that can be turned on by modes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ModeStrategy {
+    /// Underground terrain, arena statics and HasUnderground tags.
+    UndergroundArena,
+    /// Detection of neutral town and its specific script name.
+    CapturableTown,
+    /// One random hero of player's race and AvailableHeroes list.
+    OutcastHeroes,
+    /// Outcast spells and their texts.
+    OutcastMechanics,
+    /// Heroes placed by map makers are removed.
+    NoMapHeroes
+}
+
+/// Possible types of mode parameters.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ModeParamType {
+    Integer {
+        min: i64,
+        max: i64
+    },
+    Bool,
+    /// One of listed values. Written into lua as a constant.
+    Variant(Vec<String>)
+}
+
+/// Schema of single mode parameter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModeParamDefinition {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub _type: ModeParamType,
+    pub default: serde_json::Value
+}
+
+/// How mode is written into MCCS_GAME_MODES table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ModeLuaFormat {
+    /// [GAME_MODE_NAME] = 1
+    #[default]
+    Flag,
+    /// [GAME_MODE_NAME] = { param = value, ... }
+    Table
+}
+
+/// Names of quest text files of mode(they are placed in adds/win_conditions/).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModeQuestFiles {
+    pub name: String,
+    pub desc: String
+}
+
+/// Placeholder of mode description(see text::TextTemplate) filled from mode parameter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModePlaceholder {
+    pub param: String,
+    /// Values of placeholder by values of parameter, parameter's value is used as is if it isn't listed.
+    #[serde(default)]
+    pub values: HashMap<String, serde_json::Value>
+}
+
+impl ModePlaceholder {
+    /// Returns value of placeholder for given params, None if parameter isn't set.
+    pub fn value(&self, params: &ModeParams) -> Option<serde_json::Value> {
+        let param = params.get(&self.param)?;
+        let param_string = match param {
+            serde_json::Value::String(s) => s.clone(),
+            _=> param.to_string()
+        };
+        Some(self.values.get(&param_string).unwrap_or(param).clone())
+    }
+}
+
+/// Values of mode parameters, keys are parameters names.
+pub type ModeParams = HashMap<String, serde_json::Value>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModeDefinition {
+    /// For built-in modes it matches TemplateModeName string. Upper-cased it gives GAME_MODE_ constant name.
+    pub name: String,
+    #[serde(default)]
+    pub params: Vec<ModeParamDefinition>,
+    #[serde(default)]
+    pub lua: ModeLuaFormat,
+    pub quest_files: Option<ModeQuestFiles>,
+    /// Maps placeholders names of quest description to parameters they are filled from.
+    #[serde(default)]
+    pub placeholders: HashMap<String, ModePlaceholder>,
+    #[serde(default)]
+    pub strategies: Vec<ModeStrategy>
+}
+
+impl ModeDefinition {
+    /// Checks given params against schema and fills missing ones with defaults.
+    pub fn validate(&self, params: &ModeParams) -> Result<ModeParams, String> {
+        if let Some(unknown) = params.keys().find(|p| self.params.iter().any(|d| d.name == **p) == false) {
+            return Err(format!("Mode {} has no parameter {}", &self.name, unknown));
+        }
+        let mut validated = ModeParams::new();
+        for param in self.params.iter() {
+            let value = params.get(&param.name).unwrap_or(&param.default);
+            let is_valid = match &param._type {
+                ModeParamType::Integer { min, max } => {
+                    value.as_i64().is_some_and(|v| v >= *min && v <= *max)
+                },
+                ModeParamType::Bool => value.is_boolean(),
+                ModeParamType::Variant(variants) => {
+                    value.as_str().is_some_and(|v| variants.iter().any(|variant| variant == v))
+                }
+            };
+            if is_valid == false {
+                return Err(format!("Incorrect value {} of parameter {} of mode {}", value, &param.name, &self.name));
+            }
+            validated.insert(param.name.clone(), value.clone());
+        }
+        Ok(validated)
+    }
+
+    /// Returns lua presentation of mode with given params.
+    pub fn to_game_mode(&self, params: &ModeParams) -> LuaValue {
+        match self.lua {
+            ModeLuaFormat::Flag => LuaValue::Integer(1),
+            ModeLuaFormat::Table => {
+                let mut table = LuaTable::new();
+                for param in self.params.iter() {
+                    if let Some(value) = params.get(&param.name) {
+                        table = table.with_field(&param.name, param_to_lua(&param._type, value));
+                    }
+                }
+                table.into()
+            }
+        }
+    }
+}
+
+fn param_to_lua(param_type: &ModeParamType, value: &serde_json::Value) -> LuaValue {
+    match (param_type, value) {
+        (ModeParamType::Variant(_), serde_json::Value::String(s)) => LuaValue::Constant(s.clone()),
+        (_, serde_json::Value::String(s)) => LuaValue::String(s.clone()),
+        (_, serde_json::Value::Bool(b)) => LuaValue::Bool(*b),
+        (_, serde_json::Value::Number(n)) if n.is_i64() => LuaValue::Integer(n.as_i64().unwrap()),
+        (_, serde_json::Value::Number(n)) => LuaValue::Number(n.as_f64().unwrap()),
+        _=> {
+            println!("Mode parameter value {} can't be written into lua, nil is written", value);
+            LuaValue::Nil
+        }
+    }
+}
+
+/// Provides definitions of all modes found in config.
+pub struct ModeDefinitionsProvider {
+    definitions: HashMap<String, ModeDefinition>
+}
+
+impl ModeDefinitionsProvider {
+    pub fn new(config: &PathBuf) -> Self {
+        let mut definitions: HashMap<String, ModeDefinition> = HashMap::new();
+        match std::fs::read_dir(config.join("modes\\")) {
+            Ok(entries) => {
+                // sorted, so the same definition wins if names are duplicated
+                let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+                paths.sort();
+                for path in paths {
+                    if path.extension().is_some_and(|e| e == "json") == false {
+                        continue;
+                    }
+                    // one broken file must not break launcher, other modes are still available
+                    let definition_de: Result<ModeDefinition, String> = std::fs::read_to_string(&path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|definition_se| serde_json::from_str(&definition_se).map_err(|e| e.to_string()));
+                    match definition_de {
+                        Ok(definition) => {
+                            // names give GAME_MODE_ constants, so they must differ even in upper case
+                            match definitions.values().find(|d| d.name.to_uppercase() == definition.name.to_uppercase()) {
+                                Some(existing) => println!("Mode definition {:?} is skipped, its name {} is already used by mode {}", &path, &definition.name, &existing.name),
+                                None => {
+                                    definitions.insert(definition.name.clone(), definition);
+                                }
+                            }
+                        },
+                        Err(e) => println!("Error reading mode definition {:?}, it is skipped: {}", &path, e)
+                    }
+                }
+            },
+            Err(e) => println!("Error reading modes definitions: {}", e.to_string())
+        }
+        ModeDefinitionsProvider {
+            definitions: definitions
+        }
+    }
+
+    pub fn get(&self, name: &String) -> Option<&ModeDefinition> {
+        self.definitions.get(name)
+    }
+
+    pub fn definitions(&self) -> Vec<&ModeDefinition> {
+        self.definitions.values().collect()
+    }
+
+    /// Returns all strategies turned on by active modes of map(built-in and custom ones).
+    pub fn enabled_strategies(&self, map: &Map) -> Vec<ModeStrategy> {
+        let mut strategies = vec![];
+        for mode in map.active_mode_names() {
+            match self.definitions.get(&mode) {
+                Some(definition) => {
+                    for strategy in definition.strategies.iter() {
+                        if strategies.contains(strategy) == false {
+                            strategies.push(*strategy);
+                        }
+                    }
+                },
+                None => println!("No definition found for mode {}", &mode)
+            }
+        }
+        strategies
+    }
+}
@@ -1,5 +1,4 @@
 use serde::{Serialize, Deserialize};
-use crate::{patch_strategy::ProcessText, map::template::TemplateModeType};
 
 /// Final battle concrete patches.
 
@@ -11,7 +10,7 @@ pub struct FinalBattleTime {
     pub day: u8
 }
 
-/// Creates an underground arena in map if final battle is active.
+// Creates an underground arena in map if final battle is active.
 // pub struct FinalBattleArenaCreator {
 //     predefined_statics: Vec<PredefinedStatic>,
 //     final_battle_active: bool
@@ -46,26 +45,3 @@ pub struct FinalBattleTime {
 //         }
 //     }
 // }
-
-/// Writes actual timing into description file of quest of final battle.
-pub struct FinalBattleModeTextProcessor<'a> {
-    pub final_battle_time: Option<&'a TemplateModeType>
-}
-
-impl<'a> ProcessText for FinalBattleModeTextProcessor<'a> {
-    fn try_process(&self, text: &mut String) -> String {
-        if self.final_battle_time.is_some() {
-            match self.final_battle_time.as_ref().unwrap() {
-                TemplateModeType::FinalBattle(f) => {
-                    text.replace("<month>", &f.month.to_string())
-                    .replace("<week>", &f.week.to_string())
-                    .replace("<day>", &f.day.to_string())
-                },
-                _=> text.to_owned()
-            }
-        }
-        else {
-            text.to_owned()
-        }
-    }
-}
\ No newline at end of file
@@ -1,54 +1,84 @@
 pub mod final_battle;
-pub mod capture;
 pub mod economic;
 pub mod outcast;
+pub mod definition;
+pub mod text;
 
-use std::{collections::HashMap, path::PathBuf, io::Write};
-use crate::map::template::{TemplateModeType, TemplateModeName};
+use std::path::PathBuf;
+use crate::{map::Map, lua::{LuaTable, LuaKey, LuaChunk}, locale::LocalizationService};
+use self::definition::ModeDefinitionsProvider;
 use super::{WriteAdditional, GenerateLuaCode};
 
 pub struct ModesInfoGenerator<'a> {
-    modes: &'a HashMap<TemplateModeName, TemplateModeType>,
+    map: &'a Map,
+    definitions: &'a ModeDefinitionsProvider,
     config_path: &'a PathBuf,
-    write_dir: &'a PathBuf
+    write_dir: &'a PathBuf,
+    localization: &'a LocalizationService
 }
 
 impl<'a> ModesInfoGenerator<'a> {
-    pub fn new(modes: &'a HashMap<TemplateModeName, TemplateModeType>, config: &'a PathBuf, dir: &'a PathBuf) -> Self {
+    pub fn new(map: &'a Map, definitions: &'a ModeDefinitionsProvider, config: &'a PathBuf, dir: &'a PathBuf, localization: &'a LocalizationService) -> Self {
         ModesInfoGenerator {
-            modes: modes,
+            map: map,
+            definitions: definitions,
             config_path: config,
-            write_dir: dir
+            write_dir: dir,
+            localization: localization
         }
     }
 }
 
 impl<'a> GenerateLuaCode for ModesInfoGenerator<'a> {
     fn to_lua(&self, path: &PathBuf) {
-        let mut file = std::fs::File::create(path.join("modes_info.lua")).unwrap();
-        let mut modes_string = "MCCS_GAME_MODES = {\n".to_string();
-        for mode in self.modes.keys() {
-            modes_string += &format!("\t[GAME_MODE_{}] = {},\n", mode.to_string().to_uppercase(), &self.modes.get(mode).unwrap().to_game_mode());
+        let mut modes_table = LuaTable::new();
+        for mode in self.map.modes.iter() {
+            // definition decides how mode is written, built-in presentation is used only if mode has no definition
+            let game_mode = match self.definitions.get(&mode.0.to_string()) {
+                Some(definition) => definition.to_game_mode(&mode.1.to_params()),
+                None => mode.1.to_game_mode()
+            };
+            modes_table.insert(LuaKey::Constant(format!("GAME_MODE_{}", mode.0.to_string().to_uppercase())), game_mode);
         }
-        modes_string.push('}');
-        file.write_all(modes_string.as_bytes()).unwrap();
+        for mode in self.map.custom_modes.iter() {
+            if let Some(definition) = self.definitions.get(mode.0) {
+                modes_table.insert(LuaKey::Constant(format!("GAME_MODE_{}", mode.0.to_uppercase())), definition.to_game_mode(mode.1));
+            }
+        }
+        LuaChunk::new()
+            .with("MCCS_GAME_MODES", modes_table)
+            .write(path, "modes_info.lua");
     }
-}
 
-const MODES_QUESTS_FILES: [&'static str; 10] = [
-    "final_battle_name.txt", "final_battle_desc.txt", 
-    "economic_name.txt", "economic_desc.txt", 
-    "capture_object_name.txt", "capture_object_desc.txt",
-    "outcast_name.txt", "outcast_desc.txt",
-    "blitz_name.txt", "blitz_desc.txt"
-];
+    fn generated_tables(&self) -> Vec<&'static str> {
+        match self.map.modes.is_empty() && self.map.custom_modes.is_empty() {
+            true => vec![],
+            false => vec!["MCCS_GAME_MODES"]
+        }
+    }
+}
 
-/// Puts quests info into map folder
+/// Puts quests info of active modes into map folder, files of active locale are preferred(see locale::LocalizationService::localized_file)
 impl<'a> WriteAdditional for ModesInfoGenerator<'a> {
     fn try_write(&self) {
-        for file in MODES_QUESTS_FILES {
-            let path_to = self.write_dir.join(file);
-            std::fs::copy(&self.config_path.join(file), &path_to).unwrap();
+        for mode in self.map.active_mode_names() {
+            let definition = match self.definitions.get(&mode) {
+                Some(definition) => definition,
+                None => continue
+            };
+            if let Some(quest_files) = definition.quest_files.as_ref() {
+                for file in [&quest_files.name, &quest_files.desc] {
+                    let path_to = self.write_dir.join(file);
+                    let path_from = self.localization.localized_file(self.config_path, file);
+                    let copy_result = std::fs::copy(&path_from, &path_to);
+                    match copy_result {
+                        Ok(_num) => {},
+                        Err(_e) => {
+                            println!("error copying file from {:?} to {:?}", &path_from, &path_to);
+                        }
+                    }
+                }
+            }
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,337 @@
+/// Template engine of mode descriptions.
+/// Description files contain placeholders that are filled from context built from mode's parameters(see definition::ModePlaceholder):
+/// {name} - value of placeholder,
+/// {name|plural:form,form,form} - form of word for number(one, few, many for Ru and one, other for En),
+/// {name|resource} - localized name of resource type,
+/// {?name}...{/name} - section that is written only if value is set(not empty and not zero),
+/// {!name}...{/name} - section that is written only if value isn't set.
+/// Sections of different names can be nested. Errors don't stop rendering, wrong placeholders are written as is.
+
+use std::{collections::HashMap, sync::RwLock};
+use serde::Serialize;
+use crate::{
+    locale::{Locale, LocalizationService},
+    report::PatchReport,
+    patch_strategy::{ProcessText, town::NeutralTownCrossPatchInfo}
+};
+use super::definition::{ModeDefinition, ModeParams};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateValue {
+    Text(String),
+    Number(i64)
+}
+
+impl TemplateValue {
+    fn is_set(&self) -> bool {
+        match self {
+            TemplateValue::Text(text) => text.is_empty() == false,
+            TemplateValue::Number(number) => *number != 0
+        }
+    }
+
+    /// Strings give texts, numbers and bools give numbers(bools are 1 and 0), other values can't be placeholders.
+    pub fn from_json(value: &serde_json::Value) -> Option<TemplateValue> {
+        match value {
+            serde_json::Value::String(s) => Some(TemplateValue::Text(s.clone())),
+            serde_json::Value::Number(n) => n.as_i64().map(|n| TemplateValue::Number(n)),
+            serde_json::Value::Bool(b) => Some(TemplateValue::Number(*b as i64)),
+            _=> None
+        }
+    }
+
+    fn to_text(&self) -> String {
+        match self {
+            TemplateValue::Text(text) => text.clone(),
+            TemplateValue::Number(number) => number.to_string()
+        }
+    }
+}
+
+/// Values of placeholders by their names.
+#[derive(Debug, Default)]
+pub struct TemplateContext {
+    values: HashMap<String, TemplateValue>
+}
+
+impl TemplateContext {
+    pub fn new() -> Self {
+        TemplateContext {
+            values: HashMap::new()
+        }
+    }
+
+    pub fn with(mut self, name: &str, value: TemplateValue) -> Self {
+        self.insert(name, value);
+        self
+    }
+
+    pub fn insert(&mut self, name: &str, value: TemplateValue) {
+        self.values.insert(name.to_string(), value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TemplateValue> {
+        self.values.get(name)
+    }
+
+    /// Context of mode description. Placeholders of mode's definition are filled from its params,
+    /// name of neutral town isn't a parameter of any mode, so it is set for every mode if town is detected.
+    pub fn from_params(definition: &ModeDefinition, params: &ModeParams, neutral_town: &NeutralTownCrossPatchInfo) -> Self {
+        let mut context = TemplateContext::new();
+        for (name, placeholder) in definition.placeholders.iter() {
+            match placeholder.value(params).as_ref().and_then(|v| TemplateValue::from_json(v)) {
+                Some(value) => context.insert(name, value),
+                None => println!("Placeholder {} of mode {} has no value", name, &definition.name)
+            }
+        }
+        if let Some(town_name) = &neutral_town.neutral_town_name {
+            context.insert("town_name", TemplateValue::Text(town_name.clone()));
+        }
+        context
+    }
+}
+
+/// Returns form of word for given number, forms are one, few, many for Ru and one, other for En.
+fn plural_form<'f>(locale: Locale, number: i64, forms: &Vec<&'f str>) -> &'f str {
+    let number = number.abs();
+    let index = match locale {
+        Locale::Ru => {
+            if number % 10 == 1 && number % 100 != 11 {
+                0
+            }
+            else if (2..=4).contains(&(number % 10)) && (12..=14).contains(&(number % 100)) == false {
+                1
+            }
+            else {
+                2
+            }
+        },
+        Locale::En => if number == 1 {0} else {1}
+    };
+    forms.get(index).or(forms.last()).copied().unwrap_or_default()
+}
+
+pub struct TextTemplate<'a> {
+    localization: &'a LocalizationService
+}
+
+impl<'a> TextTemplate<'a> {
+    pub fn new(localization: &'a LocalizationService) -> Self {
+        TextTemplate {
+            localization: localization
+        }
+    }
+
+    /// Returns rendered text and errors found while rendering.
+    pub fn render(&self, text: &str, context: &TemplateContext) -> (String, Vec<String>) {
+        let mut errors = vec![];
+        let rendered = self.render_part(text, context, &mut errors);
+        (rendered, errors)
+    }
+
+    fn render_part(&self, text: &str, context: &TemplateContext, errors: &mut Vec<String>) -> String {
+        let mut rendered = String::new();
+        let mut rest = text;
+        while let Some(open) = rest.find('{') {
+            rendered += &rest[..open];
+            let close = match rest[open..].find('}') {
+                Some(close) => open + close,
+                None => {
+                    errors.push(format!("Placeholder isn't closed: {}", &rest[open..]));
+                    rendered += &rest[open..];
+                    return rendered;
+                }
+            };
+            let tag = &rest[open + 1..close];
+            let tail = &rest[close + 1..];
+            match tag.chars().next() {
+                Some(kind) if kind == '?' || kind == '!' => {
+                    let name = tag[1..].trim();
+                    let end_tag = format!("{{/{}}}", name);
+                    match tail.find(&end_tag) {
+                        Some(end) => {
+                            let is_set = context.get(name).is_some_and(|v| v.is_set());
+                            if is_set == (kind == '?') {
+                                rendered += &self.render_part(&tail[..end], context, errors);
+                            }
+                            rest = &tail[end + end_tag.len()..];
+                        },
+                        None => {
+                            errors.push(format!("Section {} isn't closed", name));
+                            rendered += &rest[open..=close];
+                            rest = tail;
+                        }
+                    }
+                },
+                Some('/') => {
+                    errors.push(format!("Section {} is closed, but wasn't opened", &tag[1..]));
+                    rendered += &rest[open..=close];
+                    rest = tail;
+                },
+                _=> {
+                    match self.placeholder(tag, context) {
+                        Ok(value) => rendered += &value,
+                        Err(e) => {
+                            errors.push(e);
+                            rendered += &rest[open..=close];
+                        }
+                    }
+                    rest = tail;
+                }
+            }
+        }
+        rendered += rest;
+        rendered
+    }
+
+    fn placeholder(&self, tag: &str, context: &TemplateContext) -> Result<String, String> {
+        let mut parts = tag.splitn(2, '|');
+        let name = parts.next().unwrap_or_default().trim();
+        let value = context.get(name).ok_or(format!("No value for placeholder {}", name))?;
+        match parts.next().map(|h| h.trim()) {
+            None => Ok(value.to_text()),
+            Some("resource") => Ok(self.localization.get(&format!("resources.{}", value.to_text()))),
+            Some(helper) if helper.starts_with("plural:") => {
+                let forms: Vec<&str> = helper["plural:".len()..].split(',').map(|f| f.trim()).collect();
+                match value {
+                    TemplateValue::Number(number) => Ok(plural_form(self.localization.locale(), *number, &forms).to_string()),
+                    TemplateValue::Text(_) => Err(format!("Plural form of placeholder {} is requested, but it isn't number", name))
+                }
+            },
+            Some(helper) => Err(format!("Unknown helper {} of placeholder {}", helper, name))
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ModeTextError<'a> {
+    file: &'a str,
+    error: String
+}
+
+/// Renders mode description file with context. Errors are printed and recorded into patch report.
+pub struct ModeTextProcessor<'a> {
+    template: TextTemplate<'a>,
+    context: &'a TemplateContext,
+    file: &'a str,
+    report: &'a RwLock<PatchReport>
+}
+
+impl<'a> ModeTextProcessor<'a> {
+    pub fn new(localization: &'a LocalizationService, context: &'a TemplateContext, file: &'a str, report: &'a RwLock<PatchReport>) -> Self {
+        ModeTextProcessor {
+            template: TextTemplate::new(localization),
+            context: context,
+            file: file,
+            report: report
+        }
+    }
+}
+
+impl<'a> ProcessText for ModeTextProcessor<'a> {
+    fn try_process(&self, text: &mut String) -> String {
+        let (rendered, errors) = self.template.render(text, self.context);
+        for error in errors {
+            println!("Error rendering mode text {}: {}", self.file, &error);
+            self.report.write().unwrap().add("mode_texts", &ModeTextError {
+                file: self.file,
+                error: error
+            });
+        }
+        rendered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use crate::{locale::{Locale, LocalizationService}, patch_strategy::{town::NeutralTownCrossPatchInfo, modes::definition::{ModeDefinition, ModeParams}}};
+    use super::{TemplateContext, TemplateValue, TextTemplate};
+
+    fn localization(locale: Locale) -> LocalizationService {
+        let dir = std::env::temp_dir().join(format!("btd_patcher_text_template_{:?}", std::thread::current().id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("ru.json"), r#"{"resources": {"gold": "золото", "rare": "редкие ресурсы"}}"#).unwrap();
+        std::fs::write(dir.join("en.json"), r#"{"resources": {"gold": "gold"}}"#).unwrap();
+        LocalizationService::new(&PathBuf::from(dir), locale)
+    }
+
+    #[test]
+    fn placeholders_are_filled() {
+        let localization = localization(Locale::Ru);
+        let context = TemplateContext::new()
+            .with("month", TemplateValue::Number(3))
+            .with("town_name", TemplateValue::Text("Аргос".to_string()));
+        let (text, errors) = TextTemplate::new(&localization).render("месяц {month}, город {town_name}", &context);
+        assert_eq!(text, "месяц 3, город Аргос");
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn plurals_follow_locale() {
+        let ru = localization(Locale::Ru);
+        let en = localization(Locale::En);
+        let template = "{n} {n|plural:день, дня, дней}";
+        let plural_en = "{n} {n|plural:day, days}";
+        for (n, expected_ru, expected_en) in [(1, "1 день", "1 day"), (3, "3 дня", "3 days"), (5, "5 дней", "5 days"), (11, "11 дней", "11 days"), (22, "22 дня", "22 days")] {
+            let context = TemplateContext::new().with("n", TemplateValue::Number(n));
+            assert_eq!(TextTemplate::new(&ru).render(template, &context).0, expected_ru);
+            assert_eq!(TextTemplate::new(&en).render(plural_en, &context).0, expected_en);
+        }
+    }
+
+    #[test]
+    fn resources_are_localized() {
+        let localization = localization(Locale::En);
+        let context = TemplateContext::new()
+            .with("gold", TemplateValue::Text("gold".to_string()))
+            .with("rare", TemplateValue::Text("rare".to_string()));
+        // missing string of locale is taken from default one
+        let (text, _) = TextTemplate::new(&localization).render("{gold|resource}, {rare|resource}", &context);
+        assert_eq!(text, "gold, редкие ресурсы");
+    }
+
+    #[test]
+    fn sections_are_conditional() {
+        let localization = localization(Locale::Ru);
+        let template = "{?town_name}город {town_name}{/town_name}{!town_name}нейтральный город{/town_name}{?each}{?town_name} каждого{/town_name}{/each}";
+        let with_town = TemplateContext::new()
+            .with("town_name", TemplateValue::Text("Аргос".to_string()))
+            .with("each", TemplateValue::Number(1));
+        let (text, errors) = TextTemplate::new(&localization).render(template, &with_town);
+        assert_eq!(text, "город Аргос каждого");
+        assert!(errors.is_empty());
+        let (text, errors) = TextTemplate::new(&localization).render(template, &TemplateContext::new());
+        assert_eq!(text, "нейтральный город");
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn errors_are_reported() {
+        let localization = localization(Locale::Ru);
+        let context = TemplateContext::new().with("name", TemplateValue::Text("a".to_string()));
+        let (text, errors) = TextTemplate::new(&localization).render("{missing} {name|unknown} {name|plural:x} {?name}open {/other} {", &context);
+        assert_eq!(text, "{missing} {name|unknown} {name|plural:x} {?name}open {/other} {");
+        assert_eq!(errors.len(), 6);
+    }
+
+    #[test]
+    fn context_is_filled_from_params() {
+        let localization = localization(Locale::En);
+        let definition: ModeDefinition = serde_json::from_str(r#"{
+            "name": "Economic",
+            "quest_files": null,
+            "placeholders": {
+                "res_type": { "param": "res_type", "values": { "Gold": "gold", "RareResource": "rare" } },
+                "res_count": { "param": "count" },
+                "each_resource": { "param": "res_type", "values": { "Gold": 0, "RareResource": 1 } }
+            }
+        }"#).unwrap();
+        let params: ModeParams = serde_json::from_str(r#"{"res_type": "RareResource", "count": 20}"#).unwrap();
+        let town = NeutralTownCrossPatchInfo { neutral_town_name: None };
+        let context = TemplateContext::from_params(&definition, &params, &town);
+        let (text, errors) = TextTemplate::new(&localization).render("{res_count}{?each_resource} of each of{/each_resource} {res_type|resource}", &context);
+        assert_eq!(text, "20 of each of редкие ресурсы");
+        assert!(errors.is_empty());
+    }
+}
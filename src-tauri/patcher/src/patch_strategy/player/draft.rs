@@ -0,0 +1,152 @@
+use std::{collections::HashMap, path::PathBuf, io::Write};
+use homm5_types::{town::{TownType, AdvMapTown}, player::PlayerID};
+use serde::{Serialize, Deserialize};
+use strum::IntoEnumIterator;
+use crate::{map::Map, patch_strategy::{WriteAdditional, town::TownInfoProvider}};
+use super::PlayersInfoProvider;
+
+/// Hero draft for tournaments: players ban and pick heroes in configured order.
+/// Picked heroes become the only heroes player can hire.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DraftActionType {
+    Pick,
+    Ban
+}
+
+/// Single step of draft order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DraftStep {
+    /// Number of player in map(same as in teams info).
+    pub player: usize,
+    pub action: DraftActionType
+}
+
+/// Completed draft step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DraftLogEntry {
+    pub player: usize,
+    pub action: DraftActionType,
+    /// Script name of hero.
+    pub hero: String,
+    pub race: TownType,
+    pub xdb: String
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeroDraft {
+    pub order: Vec<DraftStep>,
+    pub log: Vec<DraftLogEntry>
+}
+
+impl HeroDraft {
+    /// Every step must be made by one of map's players.
+    pub fn new(order: Vec<DraftStep>, players_count: usize) -> Result<Self, String> {
+        if let Some(step) = order.iter().find(|s| s.player == 0 || s.player > players_count) {
+            return Err(format!("Player {} of draft order isn't in map, map has {} players", step.player, players_count));
+        }
+        Ok(HeroDraft {
+            order: order,
+            log: vec![]
+        })
+    }
+
+    /// Returns step that must be done next, None if draft is over.
+    pub fn current_step(&self) -> Option<&DraftStep> {
+        self.order.get(self.log.len())
+    }
+
+    /// Hero is taken if it was already picked or banned by someone.
+    pub fn is_taken(&self, hero: &String) -> bool {
+        self.log.iter().any(|e| e.hero == *hero)
+    }
+
+    /// Makes current step with given hero. Picked hero must be of player's race if it is known(see known_players_races).
+    pub fn make_step(&mut self, hero: &String, provider: &PlayersInfoProvider, races: &HashMap<usize, TownType>) -> Result<(), String> {
+        let step = match self.current_step() {
+            Some(step) => step.clone(),
+            None => return Err("Draft is already finished".to_string())
+        };
+        if self.is_taken(hero) {
+            return Err(format!("Hero {} is already picked or banned", hero));
+        }
+        match provider.get_hero_info(hero) {
+            Some((race, _xdb)) if step.action == DraftActionType::Pick && races.get(&step.player).is_some_and(|r| r != race) => {
+                Err(format!("Hero {} is of race {:?}, but player {} plays {:?}", hero, race, step.player, races.get(&step.player).unwrap()))
+            },
+            Some((race, xdb)) => {
+                self.log.push(DraftLogEntry {
+                    player: step.player,
+                    action: step.action,
+                    hero: hero.clone(),
+                    race: *race,
+                    xdb: xdb.clone()
+                });
+                Ok(())
+            },
+            None => Err(format!("Hero {} can't be found in heroes pools", hero))
+        }
+    }
+
+    /// Heroes picked by given player.
+    pub fn picks(&self, player: usize) -> Vec<&DraftLogEntry> {
+        self.log.iter()
+            .filter(|e| e.player == player && e.action == DraftActionType::Pick)
+            .collect()
+    }
+
+    /// Heroes of every race that are still can be picked or banned.
+    pub fn pools(&self, provider: &PlayersInfoProvider) -> HashMap<TownType, Vec<String>> {
+        provider.playable_heroes.iter()
+            .map(|race| {
+                (
+                    *race.0,
+                    race.1.keys().filter(|h| self.is_taken(h) == false).cloned().collect()
+                )
+            })
+            .collect()
+    }
+}
+
+/// Races of players known before patching by numbers of players: forced ones or races of players' towns.
+pub fn known_players_races(map: &Map, town_info_provider: &TownInfoProvider) -> Result<HashMap<usize, TownType>, String> {
+    let mut races = HashMap::new();
+    for town in map.read_objects::<AdvMapTown>("AdvMapTown")? {
+        // numbers of players are positions of their ids, PlayerNone is the first one
+        let player = PlayerID::iter().position(|p| p == town.player_id).unwrap_or(0);
+        let no_xpointer_shared = town.shared.href.as_ref().unwrap().replace("#xpointer(/AdvMapTownShared)", "");
+        if let (true, Some(race)) = (player > 0, town_info_provider.get_town_type(&no_xpointer_shared)) {
+            races.entry(player).or_insert(*race);
+        }
+    }
+    for (player, start_info) in map.players_start_info.iter() {
+        if let Some(race) = start_info.race {
+            races.insert(*player, race);
+        }
+    }
+    Ok(races)
+}
+
+/// Stores draft log with patched map.
+pub struct DraftLogWriter<'a> {
+    draft: Option<&'a HeroDraft>,
+    write_dir: &'a PathBuf
+}
+
+impl<'a> DraftLogWriter<'a> {
+    pub fn new(draft: Option<&'a HeroDraft>, dir: &'a PathBuf) -> Self {
+        DraftLogWriter {
+            draft: draft,
+            write_dir: dir
+        }
+    }
+}
+
+impl<'a> WriteAdditional for DraftLogWriter<'a> {
+    fn try_write(&self) {
+        if let Some(draft) = self.draft {
+            let mut file = std::fs::File::create(self.write_dir.join("hero_draft.json")).unwrap();
+            file.write_all(serde_json::to_string_pretty(draft).unwrap().as_bytes()).unwrap();
+        }
+    }
+}
@@ -1,11 +1,14 @@
 pub mod modifiers;
+pub mod draft;
 
-use std::{collections::HashMap, path::PathBuf};
-use homm5_types::{player::Player, town::TownType};
-use rand::seq::IteratorRandom;
-use crate::map::MapTeamsCount;
+use std::{collections::HashMap, path::PathBuf, sync::RwLock};
+use homm5_types::{player::{Player, PlayerID}, town::{TownType, AdvMapTown}, hero::AdvMapHero};
+use strum::IntoEnumIterator;
+use rand::{rngs::StdRng, seq::SliceRandom};
+use serde::{Serialize, Deserialize};
+use crate::{map::{Map, MapTeamsCount}, lua::{LuaTable, LuaKey, LuaValue, LuaChunk}};
 
-use super::{PatchModifyable, PatchGroup, PatchCreatable};
+use super::{PatchModifyable, PatchGroup, PatchCreatable, GenerateLuaCode};
 
 /// Provides players info that can be used across different patches of PlayersPatchesGroup
 pub struct PlayersInfoProvider {
@@ -24,28 +27,236 @@ impl PlayersInfoProvider {
         }
     }
     /// Returns random tuple (hero_script_name, hero_xdb) of given race.
-    pub fn get_random_hero_by_race(&mut self, race: &TownType) -> (&String, &String) {
-        let possible_heroes: Vec<(&String, &String)> = self.playable_heroes.get(race).unwrap().iter()
+    /// Heroes are sorted before picking, so same rng state gives same hero.
+    pub fn get_random_hero_by_race(&mut self, race: &TownType, rng: &mut StdRng) -> (&String, &String) {
+        let mut possible_heroes: Vec<(&String, &String)> = self.playable_heroes.get(race).unwrap().iter()
             .filter(|p| self.already_selected_heroes.contains(p.0) == false)
             .collect();
-        let mut rng = rand::thread_rng();
-        let selected_hero = *possible_heroes.iter().choose(&mut rng).unwrap();
+        possible_heroes.sort();
+        let selected_hero = *possible_heroes.choose(rng).unwrap();
         self.already_selected_heroes.push(selected_hero.0.clone());
         selected_hero
     }
+
+    /// Returns race and xdb of hero with given script name.
+    pub fn get_hero_info(&self, hero: &String) -> Option<(&TownType, &String)> {
+        self.playable_heroes.iter()
+            .find_map(|race| {
+                race.1.get(hero).map(|xdb| (race.0, xdb))
+            })
+    }
+}
+
+/// Hero assigned to player by organisers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartingHero {
+    /// Script name of hero.
+    pub name: String,
+    pub race: TownType,
+    pub xdb: String
+}
+
+/// Race and starting hero forced for player(tournament rules often require it).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayerStartInfo {
+    pub race: Option<TownType>,
+    pub hero: Option<StartingHero>
+}
+
+/// Resources given to player in addition to start ones.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HandicapResources {
+    pub wood: i32,
+    pub ore: i32,
+    pub mercury: i32,
+    pub crystal: i32,
+    pub sulfur: i32,
+    pub gem: i32,
+    pub gold: i32
+}
+
+/// Creatures given to player's starting hero.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandicapCreature {
+    /// Game constant of creature(CREATURE_...).
+    pub creature: String,
+    pub count: u32
+}
+
+/// Bonuses used to balance uneven matches.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayerHandicap {
+    pub resources: HandicapResources,
+    pub creatures: Vec<HandicapCreature>,
+    /// Experience given to player's starting hero.
+    pub experience: u32
+}
+
+impl HandicapCreature {
+    pub(crate) fn to_lua(&self) -> LuaTable {
+        LuaTable::new()
+            .with_field("id", LuaValue::Constant(self.creature.clone()))
+            .with_field("count", self.count)
+    }
+}
+
+impl PlayerHandicap {
+    fn to_lua(&self) -> LuaTable {
+        let resources = [
+            ("WOOD", self.resources.wood), ("ORE", self.resources.ore), ("MERCURY", self.resources.mercury),
+            ("CRYSTAL", self.resources.crystal), ("SULFUR", self.resources.sulfur), ("GEM", self.resources.gem),
+            ("GOLD", self.resources.gold)
+        ];
+        let mut resources_table = LuaTable::new();
+        for resource in resources.iter().filter(|r| r.1 != 0) {
+            resources_table.insert(LuaKey::Constant(resource.0.to_string()), resource.1);
+        }
+        let mut creatures_table = LuaTable::new();
+        for creature in self.creatures.iter() {
+            creatures_table.push(creature.to_lua());
+        }
+        LuaTable::new()
+            .with_field("resources", resources_table)
+            .with_field("creatures", creatures_table)
+            .with_field("experience", self.experience)
+    }
+}
+
+/// Who controls player slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum PlayerControl {
+    #[default]
+    Human,
+    Ai,
+    /// Slot is removed from game.
+    Off
+}
+
+/// Behaviour of computer player, applied by map script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum AiAggressiveness {
+    Defensive,
+    #[default]
+    Normal,
+    Aggressive
+}
+
+impl AiAggressiveness {
+    fn to_lua(&self) -> u8 {
+        match self {
+            AiAggressiveness::Defensive => 0,
+            AiAggressiveness::Normal => 1,
+            AiAggressiveness::Aggressive => 2
+        }
+    }
+}
+
+/// Settings of player slot for practice maps.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayerControlInfo {
+    pub control: PlayerControl,
+    pub aggressiveness: AiAggressiveness,
+    /// Bonuses given only if slot is controlled by AI.
+    pub ai_bonus: Option<PlayerHandicap>
+}
+
+impl PlayerControlInfo {
+    /// Returns count of towns and heroes owned by player slot in map.
+    /// Slots owning objects can't be turned off, cause their objects would stay owned by player who isn't in game.
+    pub fn owned_objects_count(map: &Map, player: usize) -> Result<usize, String> {
+        let player_id = PlayerID::iter().nth(player).ok_or(format!("Unknown player {}", player))?;
+        let towns = map.read_objects::<AdvMapTown>("AdvMapTown")?;
+        let heroes = map.read_objects::<AdvMapHero>("AdvMapHero")?;
+        Ok(towns.iter().filter(|t| t.player_id == player_id).count() + heroes.iter().filter(|h| h.player_id == player_id).count())
+    }
+
+    fn to_lua(&self) -> LuaTable {
+        let mut table = LuaTable::new().with_field("aggressiveness", self.aggressiveness.to_lua());
+        if let Some(bonus) = &self.ai_bonus {
+            table = table.with_field("bonus", bonus.to_lua());
+        }
+        table
+    }
 }
 
 /// Provides players-related information that can be shared between other groups.
 pub struct PlayersCrossPatchInfo {
     /// In outcast these heroes must be written into avaliableHeroes tag
-    pub avaliable_heroes: Vec<String>
+    pub avaliable_heroes: Vec<String>,
+    /// Script names of starting heroes by numbers of players, applied by map script.
+    pub starting_heroes: HashMap<usize, String>,
+    /// Handicaps by numbers of players, applied by map script on day one(adds/scripts/handicaps.lua).
+    pub handicaps: HashMap<usize, PlayerHandicap>,
+    /// Settings of AI controlled players by numbers of players.
+    pub ai_players: HashMap<usize, PlayerControlInfo>
 }
 
 impl PlayersCrossPatchInfo {
     pub fn new() -> Self {
         PlayersCrossPatchInfo {
-            avaliable_heroes: vec![]
+            avaliable_heroes: vec![],
+            starting_heroes: HashMap::new(),
+            handicaps: HashMap::new(),
+            ai_players: HashMap::new()
+        }
+    }
+}
+
+/// Writes players related info collected while patching into players_info.lua.
+pub struct PlayersInfoGenerator<'a> {
+    cross_patch_info: &'a RwLock<PlayersCrossPatchInfo>
+}
+
+impl<'a> PlayersInfoGenerator<'a> {
+    pub fn new(info: &'a RwLock<PlayersCrossPatchInfo>) -> Self {
+        PlayersInfoGenerator {
+            cross_patch_info: info
+        }
+    }
+}
+
+impl<'a> GenerateLuaCode for PlayersInfoGenerator<'a> {
+    fn to_lua(&self, path: &PathBuf) {
+        let info = self.cross_patch_info.read().unwrap();
+        // players are sorted to keep generated file the same for the same settings
+        let mut starting_heroes_table = LuaTable::new();
+        let mut starting_heroes: Vec<_> = info.starting_heroes.iter().collect();
+        starting_heroes.sort_by_key(|h| *h.0);
+        for hero in starting_heroes {
+            starting_heroes_table.insert(LuaKey::Constant(format!("PLAYER_{}", hero.0)), hero.1);
+        }
+        let mut handicaps_table = LuaTable::new();
+        let mut handicaps: Vec<_> = info.handicaps.iter().collect();
+        handicaps.sort_by_key(|h| *h.0);
+        for handicap in handicaps {
+            handicaps_table.insert(LuaKey::Constant(format!("PLAYER_{}", handicap.0)), handicap.1.to_lua());
+        }
+        let mut ai_players_table = LuaTable::new();
+        let mut ai_players: Vec<_> = info.ai_players.iter().collect();
+        ai_players.sort_by_key(|p| *p.0);
+        for ai_player in ai_players {
+            ai_players_table.insert(LuaKey::Constant(format!("PLAYER_{}", ai_player.0)), ai_player.1.to_lua());
+        }
+        LuaChunk::new()
+            .with("BTD_StartingHeroes", starting_heroes_table)
+            .with("BTD_Handicaps", handicaps_table)
+            .with("BTD_AIPlayers", ai_players_table)
+            .write(path, "players_info.lua");
+    }
+
+    fn generated_tables(&self) -> Vec<&'static str> {
+        let info = self.cross_patch_info.read().unwrap();
+        let mut tables = vec![];
+        if info.starting_heroes.is_empty() == false {
+            tables.push("BTD_StartingHeroes");
+        }
+        if info.handicaps.is_empty() == false {
+            tables.push("BTD_Handicaps");
+        }
+        if info.ai_players.is_empty() == false {
+            tables.push("BTD_AIPlayers");
         }
+        tables
     }
 }
 
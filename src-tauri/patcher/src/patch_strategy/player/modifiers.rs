@@ -1,21 +1,32 @@
-use std::{vec, sync::RwLock};
+use std::{vec, sync::RwLock, collections::HashMap};
 
 use homm5_types::player::{Player, PlayerID, BannedHeroesRaces, AllowedHeroes};
+use rand::{SeedableRng, rngs::StdRng};
 use strum::IntoEnumIterator;
 use crate::patch_strategy::{PatchModifyable, town::PlayerRaceCrossPatchInfo};
-use super::{PlayersInfoProvider, PlayersCrossPatchInfo};
+use super::{PlayersInfoProvider, PlayersCrossPatchInfo, PlayerStartInfo, PlayerHandicap, PlayerControl, PlayerControlInfo, draft::HeroDraft};
+
+/// Bans all races in player's tavern filter, so only allowed heroes can be hired.
+fn ban_all_races(player: &mut Player) {
+    player.tavern_filter.banned_heroes_races = Some(BannedHeroesRaces {
+        items: Some(vec![
+            "TOWN_HEAVEN".to_string(), "TOWN_PRESERVE".to_string(), "TOWN_ACADEMY".to_string(), "TOWN_INFERNO".to_string(),
+            "TOWN_NECROMANCY".to_string(), "TOWN_DUNGEON".to_string(), "TOWN_FORTRESS".to_string(), "TOWN_STRONGHOLD".to_string(),
+        ])
+    });
+}
 
 /// Applies teams to active players.
 pub struct PlayerTeamSelector<'a> {
     teams_info: &'a Vec<usize>,
-    active_players_count: usize  
+    players_count: usize  
 }
 
 impl<'a> PlayerTeamSelector<'a>  {
     pub fn new(teams: &'a Vec<usize>) -> Self {
         PlayerTeamSelector { 
             teams_info: teams, 
-            active_players_count: 0 
+            players_count: 0 
         }
     }
 }
@@ -24,30 +35,33 @@ impl<'a> PatchModifyable for PlayerTeamSelector<'a>  {
     type Modifyable = Player;
 
     fn try_modify(&mut self, object: &mut Self::Modifyable) {
+        self.players_count += 1;
         if object.active_player == true {
-            self.active_players_count += 1;
-            object.team = self.teams_info[self.active_players_count];
+            object.team = self.teams_info[self.players_count];
         }
     }
 }
 
 /// In Outcast mode bans all races for player and sets only one avaliable hero.
+/// Heroes are picked with map's seed, so same seed gives same heroes.
 pub struct OutcastPlayerHeroSelector<'a> {
     is_enabled: bool,
     player_info_provider: &'a mut PlayersInfoProvider,
     player_race_provider: &'a RwLock<PlayerRaceCrossPatchInfo>,
     player_cross_patch_provider: &'a RwLock<PlayersCrossPatchInfo>,
-    active_players_count: usize
+    rng: StdRng,
+    players_count: usize
 }
 
 impl<'a> OutcastPlayerHeroSelector<'a> {
-    pub fn new(pip: &'a mut PlayersInfoProvider, prp: &'a RwLock<PlayerRaceCrossPatchInfo>, pcpp: &'a RwLock<PlayersCrossPatchInfo>, enabled: bool) -> Self  {
+    pub fn new(pip: &'a mut PlayersInfoProvider, prp: &'a RwLock<PlayerRaceCrossPatchInfo>, pcpp: &'a RwLock<PlayersCrossPatchInfo>, enabled: bool, seed: u32) -> Self  {
         OutcastPlayerHeroSelector { 
             is_enabled: enabled, 
             player_info_provider: pip,
             player_race_provider: prp,
             player_cross_patch_provider: pcpp,
-            active_players_count: 0
+            rng: StdRng::seed_from_u64(seed as u64),
+            players_count: 0
         }
     } 
 }
@@ -56,16 +70,16 @@ impl<'a> PatchModifyable for OutcastPlayerHeroSelector<'a> {
     type Modifyable = Player;
 
     fn try_modify(&mut self, object: &mut Self::Modifyable) {
+        self.players_count += 1;
         if object.active_player == true && self.is_enabled == true {
-            self.active_players_count += 1;
             // detect player's race
-            let player_id = PlayerID::iter().enumerate().find(|p| p.0 == self.active_players_count).unwrap().1;
+            let player_id = PlayerID::iter().enumerate().find(|p| p.0 == self.players_count).unwrap().1;
             let provider_borrowed = self.player_race_provider.read().unwrap();
             let race = provider_borrowed.players_race_info.get(&player_id);
             match race {
                 Some(actual_race) => {
                     // select random hero of this race
-                    let hero = self.player_info_provider.get_random_hero_by_race(actual_race);
+                    let hero = self.player_info_provider.get_random_hero_by_race(actual_race, &mut self.rng);
                     self.player_cross_patch_provider.write().unwrap().avaliable_heroes.push(hero.1.clone());
                     // let mut banned_races = vec![];
                     // TownType::iter().for_each(|t| {
@@ -74,18 +88,183 @@ impl<'a> PatchModifyable for OutcastPlayerHeroSelector<'a> {
                     //     }
                     // });
                     // ban all races
-                    object.tavern_filter.banned_heroes_races = Some(BannedHeroesRaces {
-                        items: Some(vec![
-                            "TOWN_HEAVEN".to_string(), "TOWN_PRESERVE".to_string(), "TOWN_ACADEMY".to_string(), "TOWN_INFERNO".to_string(),
-                            "TOWN_NECROMANCY".to_string(), "TOWN_DUNGEON".to_string(), "TOWN_FORTRESS".to_string(), "TOWN_STRONGHOLD".to_string(),
-                        ])
-                    });
+                    ban_all_races(object);
                     // add selected hero as only allowed
                     object.tavern_filter.allowed_heroes = Some(AllowedHeroes { 
                         items: Some(vec![hero.0.clone()])
                     });
                 }
-                None => println!("Impossible to detect race of player {}", self.active_players_count)
+                None => println!("Impossible to detect race of player {}", self.players_count)
+            }
+        }
+    }
+}
+
+/// Allows only heroes picked in draft for every active player. Works with or without Outcast mode.
+pub struct DraftHeroSelector<'a> {
+    draft: Option<&'a HeroDraft>,
+    player_race_provider: &'a RwLock<PlayerRaceCrossPatchInfo>,
+    player_cross_patch_provider: &'a RwLock<PlayersCrossPatchInfo>,
+    players_count: usize
+}
+
+impl<'a> DraftHeroSelector<'a> {
+    pub fn new(draft: Option<&'a HeroDraft>, prp: &'a RwLock<PlayerRaceCrossPatchInfo>, pcpp: &'a RwLock<PlayersCrossPatchInfo>) -> Self {
+        DraftHeroSelector {
+            draft: draft,
+            player_race_provider: prp,
+            player_cross_patch_provider: pcpp,
+            players_count: 0
+        }
+    }
+}
+
+impl<'a> PatchModifyable for DraftHeroSelector<'a> {
+    type Modifyable = Player;
+
+    fn try_modify(&mut self, object: &mut Self::Modifyable) {
+        self.players_count += 1;
+        if object.active_player == false || self.draft.is_none() {
+            return;
+        }
+        let player_id = PlayerID::iter().enumerate().find(|p| p.0 == self.players_count).unwrap().1;
+        let race = self.player_race_provider.read().unwrap().players_race_info.get(&player_id).cloned();
+        let picks = self.draft.unwrap().picks(self.players_count);
+        // picks are checked while drafting, race can differ only if it was forced after draft, hero is kept anyway
+        for pick in picks.iter().filter(|pick| race.is_some_and(|r| r != pick.race)) {
+            println!("Hero {} picked by player {} doesn't match player's race", &pick.hero, self.players_count);
+        }
+        if picks.is_empty() {
+            println!("Player {} has no heroes picked in draft", self.players_count);
+            return;
+        }
+        let mut cross_patch_info = self.player_cross_patch_provider.write().unwrap();
+        for pick in picks.iter() {
+            cross_patch_info.avaliable_heroes.push(pick.xdb.clone());
+        }
+        ban_all_races(object);
+        object.tavern_filter.allowed_heroes = Some(AllowedHeroes {
+            items: Some(picks.iter().map(|pick| pick.hero.clone()).collect())
+        });
+    }
+}
+
+/// Passes starting heroes assigned by organisers to map script, hero itself is set on map by hero::modifiers::StartingHeroApplier.
+pub struct StartingHeroSelector<'a> {
+    start_info: &'a HashMap<usize, PlayerStartInfo>,
+    player_race_provider: &'a RwLock<PlayerRaceCrossPatchInfo>,
+    player_cross_patch_provider: &'a RwLock<PlayersCrossPatchInfo>,
+    players_count: usize
+}
+
+impl<'a> StartingHeroSelector<'a> {
+    pub fn new(start_info: &'a HashMap<usize, PlayerStartInfo>, prp: &'a RwLock<PlayerRaceCrossPatchInfo>, pcpp: &'a RwLock<PlayersCrossPatchInfo>) -> Self {
+        StartingHeroSelector {
+            start_info: start_info,
+            player_race_provider: prp,
+            player_cross_patch_provider: pcpp,
+            players_count: 0
+        }
+    }
+}
+
+impl<'a> PatchModifyable for StartingHeroSelector<'a> {
+    type Modifyable = Player;
+
+    fn try_modify(&mut self, object: &mut Self::Modifyable) {
+        self.players_count += 1;
+        if object.active_player == false {
+            return;
+        }
+        let hero = self.start_info.get(&self.players_count).and_then(|i| i.hero.as_ref());
+        if let Some(actual_hero) = hero {
+            let player_id = PlayerID::iter().enumerate().find(|p| p.0 == self.players_count).unwrap().1;
+            let race = self.player_race_provider.read().unwrap().players_race_info.get(&player_id).cloned();
+            if race.is_some_and(|r| r != actual_hero.race) {
+                println!("Starting hero {} doesn't match race of player {}", &actual_hero.name, self.players_count);
+                return;
+            }
+            let mut cross_patch_info = self.player_cross_patch_provider.write().unwrap();
+            cross_patch_info.starting_heroes.insert(self.players_count, actual_hero.name.clone());
+            if cross_patch_info.avaliable_heroes.contains(&actual_hero.xdb) == false {
+                cross_patch_info.avaliable_heroes.push(actual_hero.xdb.clone());
+            }
+        }
+    }
+}
+
+/// Applies handicaps to active players.
+/// Player tag has no place for start bonuses, so they are passed to map script that gives them on day one.
+pub struct PlayerHandicapApplier<'a> {
+    handicaps: &'a HashMap<usize, PlayerHandicap>,
+    player_cross_patch_provider: &'a RwLock<PlayersCrossPatchInfo>,
+    players_count: usize
+}
+
+impl<'a> PlayerHandicapApplier<'a> {
+    pub fn new(handicaps: &'a HashMap<usize, PlayerHandicap>, pcpp: &'a RwLock<PlayersCrossPatchInfo>) -> Self {
+        PlayerHandicapApplier {
+            handicaps: handicaps,
+            player_cross_patch_provider: pcpp,
+            players_count: 0
+        }
+    }
+}
+
+impl<'a> PatchModifyable for PlayerHandicapApplier<'a> {
+    type Modifyable = Player;
+
+    fn try_modify(&mut self, object: &mut Self::Modifyable) {
+        self.players_count += 1;
+        if object.active_player == true {
+            if let Some(handicap) = self.handicaps.get(&self.players_count) {
+                self.player_cross_patch_provider.write().unwrap().handicaps.insert(self.players_count, handicap.clone());
+            }
+        }
+    }
+}
+
+/// Sets who controls player slots.
+/// Only slots that are active in map can be changed, cause inactive ones have no towns and heroes.
+/// Slots can be turned off only if they own no towns and heroes(see PlayerControlInfo::owned_objects_count).
+pub struct PlayerControlApplier<'a> {
+    control_info: &'a HashMap<usize, PlayerControlInfo>,
+    player_cross_patch_provider: &'a RwLock<PlayersCrossPatchInfo>,
+    players_count: usize
+}
+
+impl<'a> PlayerControlApplier<'a> {
+    pub fn new(control_info: &'a HashMap<usize, PlayerControlInfo>, pcpp: &'a RwLock<PlayersCrossPatchInfo>) -> Self {
+        PlayerControlApplier {
+            control_info: control_info,
+            player_cross_patch_provider: pcpp,
+            players_count: 0
+        }
+    }
+}
+
+impl<'a> PatchModifyable for PlayerControlApplier<'a> {
+    type Modifyable = Player;
+
+    fn try_modify(&mut self, object: &mut Self::Modifyable) {
+        self.players_count += 1;
+        if object.active_player == false {
+            return;
+        }
+        if let Some(info) = self.control_info.get(&self.players_count) {
+            match info.control {
+                PlayerControl::Human => {
+                    object.can_be_human = true;
+                    object.can_be_computer = false;
+                },
+                PlayerControl::Ai => {
+                    object.can_be_human = false;
+                    object.can_be_computer = true;
+                    self.player_cross_patch_provider.write().unwrap().ai_players.insert(self.players_count, info.clone());
+                },
+                PlayerControl::Off => {
+                    object.active_player = false;
+                }
             }
         }
     }
@@ -1,4 +1,5 @@
 pub mod modifiers;
+pub mod objectives;
 
 use std::path::PathBuf;
 use serde::{Serialize, Deserialize};
@@ -39,22 +40,18 @@ pub struct ObjectivesInfo {
 impl Homm5Type for ObjectivesInfo {
 }
 
-/// Provides information that can be used across different patches in QuestPatchesGroup
+/// Provides information that can be used across different patches in QuestPatchesGroup.
+/// Primary quests of modes are generated by objectives::ObjectivesProvider.
 pub struct QuestInfoProvider {
-    primary_quests: Vec<Quest>,
     secondary_quests: Vec<Quest>
 }
 
 impl QuestInfoProvider {
     pub fn new(config: &PathBuf) -> Self {
-        let primary_de: Vec<Quest> = quick_xml::de::from_str(
-            &std::fs::read_to_string(config.join("primary_quests.xml")).unwrap()
-        ).unwrap();
         let secondary_de: Vec<Quest> = quick_xml::de::from_str(
             &std::fs::read_to_string(config.join("secondary_quests.xml")).unwrap()
         ).unwrap();
         QuestInfoProvider { 
-            primary_quests: primary_de, 
             secondary_quests: secondary_de
         }
     }
@@ -1,6 +1,6 @@
-use homm5_types::quest::Objectives;
+use homm5_types::quest::{Objectives, Quest, QuestList};
 use crate::patch_strategy::PatchModifyable;
-use super::{QuestInfoProvider, ObjectivesInfo};
+use super::{QuestInfoProvider, ObjectivesInfo, objectives::{GeneratedObjectives, ObjectiveKind}};
 
 
 /// Adds HIDDEN quest which is used to trigger map script initialization.
@@ -38,36 +38,56 @@ impl<'a> PatchModifyable for MapInitQuestCreator<'a> {
     }
 }
 
-/// Adds quests-descriptions for map modes
-pub struct MapModesQuestCreator<'a> {
-    quest_info_provider: &'a QuestInfoProvider
+/// Adds quests to list, creating its objectives if they are empty.
+fn add_quests(list: &mut QuestList, quests: &Vec<Quest>) {
+    match list.objectives.as_mut() {
+        Some(objectives) => {
+            match objectives.items.as_mut() {
+                Some(items) => items.extend(quests.iter().cloned()),
+                None => objectives.items = Some(quests.clone())
+            }
+        },
+        None => {
+            list.objectives = Some(Objectives { 
+                items: Some(quests.clone()) 
+            })
+        }
+    }
 }
 
-impl<'a> MapModesQuestCreator<'a>  {
-    pub fn new(qip: &'a QuestInfoProvider) -> Self {
-        MapModesQuestCreator { 
-            quest_info_provider: qip
+/// Adds objectives generated for active modes of map, common ones and ones of every active player.
+pub struct ModesObjectivesCreator<'a> {
+    objectives: &'a GeneratedObjectives
+}
+
+impl<'a> ModesObjectivesCreator<'a> {
+    pub fn new(objectives: &'a GeneratedObjectives) -> Self {
+        ModesObjectivesCreator { 
+            objectives: objectives
         }
     }
 }
 
-impl<'a> PatchModifyable for MapModesQuestCreator<'a> {
+impl<'a> PatchModifyable for ModesObjectivesCreator<'a> {
     type Modifyable = ObjectivesInfo;
 
     fn try_modify(&mut self, object: &mut Self::Modifyable) {
-        match object.primary.common.as_mut().unwrap().objectives.as_mut() {
-            Some(objectives) => {
-                for quest in self.quest_info_provider.primary_quests.iter() {
-                    match objectives.items.as_mut() {
-                        Some(items) => items.push(quest.clone()),
-                        None => objectives.items = Some(vec![quest.clone()])
+        for kind in [ObjectiveKind::Primary, ObjectiveKind::Secondary] {
+            let (common, player_specific) = match kind {
+                ObjectiveKind::Primary => (&mut object.primary.common, &mut object.primary.player_specific),
+                ObjectiveKind::Secondary => (&mut object.secondary.common, &mut object.secondary.player_specific)
+            };
+            if let Some(quests) = self.objectives.common.get(&kind) {
+                add_quests(common.as_mut().unwrap(), quests);
+            }
+            if let Some(players_quests) = self.objectives.player_specific.get(&kind) {
+                for player_quests in players_quests.iter() {
+                    // lists of players start with first player
+                    match player_specific.items.as_mut().and_then(|items| items.get_mut(*player_quests.0 - 1)) {
+                        Some(list) => add_quests(list, player_quests.1),
+                        None => println!("Map has no objectives list for player {}", player_quests.0)
                     }
                 }
-            },
-            None => {
-                object.primary.common.as_mut().unwrap().objectives = Some(Objectives { 
-                    items: Some(self.quest_info_provider.primary_quests.clone()) 
-                })
             }
         }
     }
@@ -0,0 +1,168 @@
+use std::{collections::HashMap, path::PathBuf};
+use homm5_types::quest::Quest;
+use serde::{Serialize, Deserialize};
+
+use crate::{map::Map, patch_strategy::modes::definition::ModeDefinitionsProvider};
+
+/// Objectives of map are generated from objectives/objectives.json, so map contains only quests its modes need.
+/// Every objective is built from xml template of quest, where {name}, {caption}, {description} and {player}
+/// placeholders are replaced with objective's values.
+/// Template file can contain several quests(like win_condition_quests.xml), quest named as objective is used then.
+
+/// List of objectives quest is placed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ObjectiveKind {
+    Primary,
+    Secondary
+}
+
+/// Common objectives are shared by all players, player specific ones are created for every active player
+/// (lose conditions like "lose if capital is lost" for example).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ObjectiveScope {
+    Common,
+    PlayerSpecific
+}
+
+/// Where caption and description text files of objective are taken from.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum ObjectiveTexts {
+    /// Quest files of mode with this name(from its definition).
+    Mode(String),
+    Files {
+        caption: String,
+        description: String
+    },
+    #[default]
+    None
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectiveDefinition {
+    /// script name of quest, can contain {player} placeholder for player specific objectives.
+    pub name: String,
+    /// file of quest template in objectives/, its quest with objective's name is used or the first one if there is no such quest.
+    pub template: String,
+    pub kind: ObjectiveKind,
+    pub scope: ObjectiveScope,
+    /// at least one of these modes must be active(names are same as in modes definitions), None means always.
+    pub modes: Option<Vec<String>>,
+    #[serde(default)]
+    pub texts: ObjectiveTexts
+}
+
+/// Quests generated for map.
+#[derive(Debug, Default)]
+pub struct GeneratedObjectives {
+    pub common: HashMap<ObjectiveKind, Vec<Quest>>,
+    /// quests by numbers of players.
+    pub player_specific: HashMap<ObjectiveKind, HashMap<usize, Vec<Quest>>>
+}
+
+/// Returns quest of template file that has given name, or the first quest of file.
+fn select_template_quest(template: &String, name: &str) -> String {
+    let quests: Vec<String> = template.split("</Item>")
+        .filter(|q| q.trim().is_empty() == false)
+        .map(|q| format!("{}</Item>", q.trim()))
+        .collect();
+    let name_tag = format!("<Name>{}</Name>", quick_xml::escape::escape(name));
+    quests.iter()
+        .find(|q| q.contains(&name_tag))
+        .or(quests.first())
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Provides objectives definitions and templates listed in config.
+pub struct ObjectivesProvider {
+    objectives_dir: PathBuf,
+    definitions: Vec<ObjectiveDefinition>
+}
+
+impl ObjectivesProvider {
+    pub fn new(config: &PathBuf) -> Self {
+        let objectives_dir = config.join("objectives\\");
+        let definitions_de: Result<Vec<ObjectiveDefinition>, String> = std::fs::read_to_string(objectives_dir.join("objectives.json"))
+            .map_err(|e| e.to_string())
+            .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()));
+        let definitions = match definitions_de {
+            Ok(definitions) => definitions,
+            Err(e) => {
+                println!("Error reading objectives config: {}", e);
+                vec![]
+            }
+        };
+        ObjectivesProvider {
+            objectives_dir: objectives_dir,
+            definitions: definitions
+        }
+    }
+
+    /// Creates quests of all objectives active modes of map need.
+    pub fn generate(&self, map: &Map, modes: &ModeDefinitionsProvider) -> GeneratedObjectives {
+        let mut generated = GeneratedObjectives::default();
+        let active_modes = map.active_mode_names();
+        // player's number is same as index of teams info, players without team aren't active
+        let active_players: Vec<usize> = map.teams_info.iter().enumerate()
+            .skip(1)
+            .filter(|p| *p.1 != 0)
+            .map(|p| p.0)
+            .collect();
+        for definition in self.definitions.iter() {
+            if definition.modes.as_ref().is_some_and(|m| m.iter().any(|m| active_modes.contains(m)) == false) {
+                continue;
+            }
+            let template = match std::fs::read_to_string(self.objectives_dir.join(&definition.template)) {
+                Ok(template) => select_template_quest(&template, &definition.name),
+                Err(e) => {
+                    println!("Error reading template {} of objective {}: {}", &definition.template, &definition.name, e.to_string());
+                    continue;
+                }
+            };
+            let (caption, description) = match &definition.texts {
+                ObjectiveTexts::Mode(mode) => {
+                    match modes.get(mode).and_then(|d| d.quest_files.as_ref()) {
+                        Some(files) => (files.name.clone(), files.desc.clone()),
+                        None => {
+                            println!("Mode {} of objective {} has no quest files", mode, &definition.name);
+                            (String::new(), String::new())
+                        }
+                    }
+                },
+                ObjectiveTexts::Files { caption, description } => (caption.clone(), description.clone()),
+                ObjectiveTexts::None => (String::new(), String::new())
+            };
+            let build = |player: Option<usize>| -> Option<Quest> {
+                let player = player.map(|p| p.to_string()).unwrap_or_default();
+                let quest_text = template
+                    .replace("{name}", &quick_xml::escape::escape(&definition.name.replace("{player}", &player)))
+                    .replace("{caption}", &quick_xml::escape::escape(&caption))
+                    .replace("{description}", &quick_xml::escape::escape(&description))
+                    .replace("{player}", &player);
+                match quick_xml::de::from_str::<Quest>(&quest_text) {
+                    Ok(quest) => Some(quest),
+                    Err(e) => {
+                        println!("Error deserializing quest of objective {}: {}", &definition.name, e.to_string());
+                        None
+                    }
+                }
+            };
+            match definition.scope {
+                ObjectiveScope::Common => {
+                    if let Some(quest) = build(None) {
+                        generated.common.entry(definition.kind).or_insert(vec![]).push(quest);
+                    }
+                },
+                ObjectiveScope::PlayerSpecific => {
+                    for player in active_players.iter() {
+                        if let Some(quest) = build(Some(*player)) {
+                            generated.player_specific.entry(definition.kind).or_insert(HashMap::new())
+                                .entry(*player).or_insert(vec![]).push(quest);
+                        }
+                    }
+                }
+            }
+        }
+        generated
+    }
+}
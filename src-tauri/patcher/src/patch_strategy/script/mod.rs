@@ -0,0 +1,196 @@
+use std::{path::PathBuf, io::Write, sync::RwLock};
+use serde::{Serialize, Deserialize};
+
+use crate::{map::{Map, MapSettingFlag}, report::PatchReport};
+use super::WriteAdditional;
+
+/// MapScript.lua is assembled from lua modules listed in adds/scripts/modules.json.
+/// Core module is always included, others are included only if map needs them(active modes, settings or generated tables),
+/// so mode specific code ships only with maps that use it. Modules are ordered by their dependencies.
+
+/// Module is included if all of its conditions are true. None condition is always true.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ScriptModuleConditions {
+    /// at least one of these modes must be active(names are same as in modes definitions).
+    modes: Option<Vec<String>>,
+    /// all of these settings must be set.
+    settings: Option<Vec<MapSettingFlag>>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScriptModule {
+    pub name: String,
+    /// lua file of module in adds/scripts/.
+    pub file: String,
+    /// names of modules that must be placed before this one.
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    #[serde(default)]
+    conditions: ScriptModuleConditions,
+    /// generated tables(BTD_Portals for example) this module handles.
+    /// If any of them is generated, module is required and its conditions aren't checked.
+    #[serde(default)]
+    pub tables: Vec<String>,
+    /// module is included when its conditions match even if none of its tables is generated(core for example).
+    #[serde(default)]
+    pub required: bool
+}
+
+impl ScriptModule {
+    fn is_needed(&self, map: &Map, active_modes: &Vec<String>, generated_tables: &Vec<&str>) -> bool {
+        let conditions_match =
+            (self.conditions.modes.is_none() || self.conditions.modes.as_ref().unwrap().iter().any(|m| active_modes.contains(m))) &&
+            (self.conditions.settings.is_none() || self.conditions.settings.as_ref().unwrap().iter().all(|s| map.settings.is_set(s)));
+        let tables_generated = self.tables.iter().any(|t| generated_tables.contains(&t.as_str()));
+        // modules that handle tables are only needed with these tables, unless they are required anyway
+        match self.tables.is_empty() || self.required == true {
+            true => conditions_match || tables_generated,
+            false => tables_generated
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ScriptModulesModel {
+    modules: Vec<ScriptModule>
+}
+
+/// Result of assembly: names of included modules in order they are placed in script, its code and problems found.
+#[derive(Debug, Default, Serialize)]
+pub struct ScriptAssembly {
+    pub modules: Vec<String>,
+    #[serde(skip)]
+    pub code: String,
+    pub issues: Vec<String>
+}
+
+/// Provides script modules listed in config.
+pub struct ScriptModulesProvider {
+    modules_dir: PathBuf,
+    modules: Vec<ScriptModule>,
+    load_error: Option<String>
+}
+
+impl ScriptModulesProvider {
+    pub fn new(config: &PathBuf) -> Self {
+        let modules_dir = config.join("adds\\scripts\\");
+        let modules_de = std::fs::read_to_string(modules_dir.join("modules.json"))
+            .map_err(|e| e.to_string())
+            .and_then(|s| serde_json::from_str::<ScriptModulesModel>(&s).map_err(|e| e.to_string()));
+        match modules_de {
+            Ok(model) => ScriptModulesProvider {
+                modules_dir: modules_dir,
+                modules: model.modules,
+                load_error: None
+            },
+            Err(e) => {
+                println!("Error reading script modules: {}", &e);
+                ScriptModulesProvider {
+                    modules_dir: modules_dir,
+                    modules: vec![],
+                    load_error: Some(e)
+                }
+            }
+        }
+    }
+
+    /// Selects modules needed by map, adds their dependencies and orders them so every module goes after its dependencies.
+    /// generated_tables are names of not empty lua tables that will be generated for map.
+    pub fn assemble(&self, map: &Map, generated_tables: &Vec<&str>) -> ScriptAssembly {
+        let mut assembly = ScriptAssembly::default();
+        if let Some(error) = &self.load_error {
+            assembly.issues.push(format!("Script modules config can't be read: {}", error));
+        }
+        let active_modes = map.active_mode_names();
+        let mut included: Vec<&ScriptModule> = self.modules.iter()
+            .filter(|m| m.is_needed(map, &active_modes, generated_tables))
+            .collect();
+        // dependencies are included even if their own conditions are false
+        let mut checked = 0;
+        while checked < included.len() {
+            for dependency in included[checked].dependencies.clone() {
+                if included.iter().any(|m| m.name == dependency) {
+                    continue;
+                }
+                match self.modules.iter().find(|m| m.name == dependency) {
+                    Some(module) => included.push(module),
+                    None => assembly.issues.push(format!("Module {} depends on unknown module {}", &included[checked].name, &dependency))
+                }
+            }
+            checked += 1;
+        }
+        // every generated table must be declared by some module which file exists
+        for table in generated_tables.iter() {
+            let handlers: Vec<&ScriptModule> = self.modules.iter().filter(|m| m.tables.iter().any(|t| t == table)).collect();
+            if handlers.is_empty() == true {
+                assembly.issues.push(format!("Table {} is generated, but no module handles it", table));
+            }
+            else if handlers.iter().all(|h| self.modules_dir.join(&h.file).exists() == false) {
+                assembly.issues.push(format!(
+                    "Table {} is generated, but files of modules handling it({}) are missing",
+                    table,
+                    handlers.iter().map(|h| h.name.as_str()).collect::<Vec<&str>>().join(", ")
+                ));
+            }
+        }
+        // modules are placed in config order when their dependencies are already placed
+        included.sort_by_key(|m| self.modules.iter().position(|c| c.name == m.name));
+        let mut ordered: Vec<&ScriptModule> = vec![];
+        while ordered.len() < included.len() {
+            let next = included.iter()
+                .find(|m| {
+                    ordered.iter().any(|o| o.name == m.name) == false &&
+                        m.dependencies.iter().all(|d| ordered.iter().any(|o| o.name == *d) || included.iter().any(|i| i.name == *d) == false)
+                });
+            match next {
+                Some(module) => ordered.push(module),
+                None => {
+                    let cycled: Vec<&str> = included.iter()
+                        .filter(|m| ordered.iter().any(|o| o.name == m.name) == false)
+                        .map(|m| m.name.as_str())
+                        .collect();
+                    assembly.issues.push(format!("Modules {} have cyclic dependencies and aren't included", cycled.join(", ")));
+                    break;
+                }
+            }
+        }
+        for module in ordered {
+            match std::fs::read_to_string(self.modules_dir.join(&module.file)) {
+                Ok(code) => {
+                    assembly.code += &format!("-- module {}\n{}\n\n", &module.name, code);
+                    assembly.modules.push(module.name.clone());
+                },
+                Err(e) => assembly.issues.push(format!("Module {} can't be read from {}: {}", &module.name, &module.file, e.to_string()))
+            }
+        }
+        assembly
+    }
+}
+
+/// Writes assembled MapScript.lua into map and records assembly result into patch report.
+pub struct MapScriptAssembler<'a> {
+    assembly: &'a ScriptAssembly,
+    report: &'a RwLock<PatchReport>,
+    write_dir: &'a PathBuf
+}
+
+impl<'a> MapScriptAssembler<'a> {
+    pub fn new(assembly: &'a ScriptAssembly, report: &'a RwLock<PatchReport>, dir: &'a PathBuf) -> Self {
+        MapScriptAssembler {
+            assembly: assembly,
+            report: report,
+            write_dir: dir
+        }
+    }
+}
+
+impl<'a> WriteAdditional for MapScriptAssembler<'a> {
+    fn try_write(&self) {
+        for issue in self.assembly.issues.iter() {
+            println!("Map script assembly: {}", issue);
+        }
+        self.report.write().unwrap().add("map_script", self.assembly);
+        let mut file = std::fs::File::create(self.write_dir.join("MapScript.lua")).unwrap();
+        file.write_all(self.assembly.code.as_bytes()).unwrap();
+    }
+}
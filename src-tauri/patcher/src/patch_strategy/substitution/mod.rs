@@ -0,0 +1,179 @@
+use std::{path::PathBuf, sync::RwLock, marker::PhantomData};
+use homm5_types::{building::AdvMapBuilding, treasure::AdvMapTreasure, creature::AdvMapMonster, hero::AdvMapHero, common::FileRef};
+use serde::{Serialize, Deserialize};
+
+use crate::{map::{Map, MapSettingFlag}, report::PatchReport};
+use super::PatchModifyable;
+
+/// Substitutions replace shareds of map objects with other ones or remove objects at all.
+/// They are listed in substitutions.json, so new swaps(banks types, dwellings, shrines...) don't need new code.
+
+/// Map objects whose shared can be substituted.
+pub trait Substitutable {
+    /// Name of shared type used in xpointer of object's shared.
+    const SHARED_TYPE: &'static str;
+
+    fn shared(&mut self) -> &mut FileRef;
+
+    /// Name of object for patch report.
+    fn report_name(&self) -> String;
+}
+
+impl Substitutable for AdvMapBuilding {
+    const SHARED_TYPE: &'static str = "AdvMapBuildingShared";
+
+    fn shared(&mut self) -> &mut FileRef {
+        &mut self.shared
+    }
+
+    fn report_name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl Substitutable for AdvMapTreasure {
+    const SHARED_TYPE: &'static str = "AdvMapTreasureShared";
+
+    fn shared(&mut self) -> &mut FileRef {
+        &mut self.shared
+    }
+
+    fn report_name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl Substitutable for AdvMapMonster {
+    const SHARED_TYPE: &'static str = "AdvMapMonsterShared";
+
+    fn shared(&mut self) -> &mut FileRef {
+        &mut self.shared
+    }
+
+    fn report_name(&self) -> String {
+        self.name.clone().unwrap_or_default()
+    }
+}
+
+impl Substitutable for AdvMapHero {
+    const SHARED_TYPE: &'static str = "AdvMapHeroShared";
+
+    fn shared(&mut self) -> &mut FileRef {
+        &mut self.shared
+    }
+
+    fn report_name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+/// Substitution is active only if all of its conditions are true. None condition is always true.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SubstitutionConditions {
+    /// at least one of these modes must be active(names are same as in modes definitions).
+    modes: Option<Vec<String>>,
+    /// all of these settings must be set.
+    settings: Option<Vec<MapSettingFlag>>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Substitution {
+    /// shared of object without xpointer.
+    pub from: String,
+    /// new shared of object without xpointer, None means object must be removed.
+    pub to: Option<String>,
+    #[serde(default)]
+    conditions: SubstitutionConditions
+}
+
+impl Substitution {
+    fn is_active(&self, map: &Map) -> bool {
+        let active_modes = map.active_mode_names();
+        (self.conditions.modes.is_none() || self.conditions.modes.as_ref().unwrap().iter().any(|m| active_modes.contains(m))) &&
+            (self.conditions.settings.is_none() || self.conditions.settings.as_ref().unwrap().iter().all(|s| map.settings.is_set(s)))
+    }
+}
+
+/// Provides substitutions listed in config.
+pub struct SubstitutionsProvider {
+    substitutions: Vec<Substitution>
+}
+
+impl SubstitutionsProvider {
+    pub fn new(config: &PathBuf) -> Self {
+        let substitutions_de: Vec<Substitution> = serde_json::from_str(
+            &std::fs::read_to_string(config.join("substitutions.json")).unwrap()
+        ).unwrap();
+        SubstitutionsProvider { 
+            substitutions: substitutions_de 
+        }
+    }
+
+    /// Returns substitutions whose conditions are true for map.
+    pub fn get_active(&self, map: &Map) -> Vec<&Substitution> {
+        self.substitutions.iter()
+            .filter(|s| s.is_active(map))
+            .collect()
+    }
+}
+
+#[derive(Serialize)]
+struct SubstitutionRecord {
+    object: String,
+    from: String,
+    to: Option<String>
+}
+
+/// Applies active substitutions to objects of some type. Every substitution is recorded in patch report.
+/// Must be applied after name appliers to make report readable.
+pub struct ObjectSubstitutor<'a, T: Substitutable> {
+    substitutions: Vec<&'a Substitution>,
+    report: &'a RwLock<PatchReport>,
+    removal_requested: bool,
+    _object: PhantomData<T>
+}
+
+impl<'a, T: Substitutable> ObjectSubstitutor<'a, T> {
+    pub fn new(substitutions: Vec<&'a Substitution>, report: &'a RwLock<PatchReport>) -> Self {
+        ObjectSubstitutor {
+            substitutions: substitutions,
+            report: report,
+            removal_requested: false,
+            _object: PhantomData
+        }
+    }
+}
+
+impl<'a, T: Substitutable> PatchModifyable for ObjectSubstitutor<'a, T> {
+    type Modifyable = T;
+
+    fn try_modify(&mut self, object: &mut Self::Modifyable) {
+        self.removal_requested = false;
+        let xpointer = format!("#xpointer(/{})", T::SHARED_TYPE);
+        let no_xpointer_shared = match object.shared().href.as_ref() {
+            Some(href) => href.replace(&xpointer, ""),
+            None => return
+        };
+        if let Some(substitution) = self.substitutions.iter().find(|s| s.from == no_xpointer_shared) {
+            match &substitution.to {
+                Some(new_shared) => {
+                    *object.shared() = FileRef {
+                        href: Some(format!("{}{}", new_shared, &xpointer))
+                    };
+                },
+                None => {
+                    self.removal_requested = true;
+                }
+            }
+            self.report.write().unwrap().add("substitutions", &SubstitutionRecord {
+                object: object.report_name(),
+                from: substitution.from.clone(),
+                to: substitution.to.clone()
+            });
+        }
+    }
+
+    fn is_removal_requested(&self) -> bool {
+        self.removal_requested
+    }
+}
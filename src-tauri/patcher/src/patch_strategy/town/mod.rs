@@ -1,14 +1,16 @@
-mod town_scheme;
+pub mod town_scheme;
 pub mod modifiers;
 pub mod getters;
 
-use std::{collections::HashMap, path::PathBuf, io::Write, vec};
+use std::{collections::HashMap, path::PathBuf, vec};
 use homm5_types::{
-    town::{TownType, AdvMapTown, TownBuildings},
+    town::{TownType, AdvMapTown, TownBuildings, TownBuilding, TownBuildingType},
     player::PlayerID
 };
 
-use self::{town_scheme::TownBuildingScheme, getters::{TownGameInfo, Point}};
+use crate::{map::{Map, template::TemplateModeName}, lua::{LuaTable, LuaKey, LuaChunk}};
+
+use self::{town_scheme::{TownBuildingScheme, SchemedTownBuilding}, getters::{TownGameInfo, Point}};
 
 use super::{PatchModifyable, GenerateLuaCode, PatchGetter, PatchGroup};
 
@@ -20,7 +22,11 @@ pub struct TownInfoProvider {
     // maps towns specializations to their names
     town_specs: HashMap<String, String>,
     // possible building schemes
-    town_building_schemes: HashMap<String, TownBuildingScheme>
+    town_building_schemes: HashMap<String, TownBuildingScheme>,
+    // error of schemes file reading, if any
+    schemes_error: Option<String>,
+    // buildings every town type has
+    available_buildings: HashMap<TownType, Vec<TownBuildingType>>
 }
 
 impl TownInfoProvider {
@@ -31,23 +37,112 @@ impl TownInfoProvider {
         let specs_se = std::fs::read_to_string(config_path.join("town_specs.json")).unwrap();
         let specs_de: HashMap<String, String> = serde_json::from_str(&specs_se).unwrap();
         //
-        let schemes_se = std::fs::read_to_string(config_path.join("town_build_schemes.json")).unwrap();
-        let schemes_de: HashMap<String, TownBuildingScheme> = serde_json::from_str(&schemes_se).unwrap();
+        // schemes are edited by hand often, so errors must not break whole patching process.
+        let schemes_de: Result<HashMap<String, TownBuildingScheme>, String> = std::fs::read_to_string(config_path.join("town_build_schemes.json"))
+            .map_err(|e| e.to_string())
+            .and_then(|schemes_se| serde_json::from_str(&schemes_se).map_err(|e| e.to_string()));
+        let (schemes, schemes_error) = match schemes_de {
+            Ok(mut schemes) => {
+                town_scheme::migrate_schemes(&mut schemes);
+                (schemes, None)
+            },
+            Err(e) => {
+                println!("Error reading town building schemes: {}", &e);
+                (HashMap::new(), Some(e))
+            }
+        };
+        //
+        let buildings_de: Result<HashMap<TownType, Vec<TownBuildingType>>, String> = std::fs::read_to_string(config_path.join("town_available_buildings.json"))
+            .map_err(|e| e.to_string())
+            .and_then(|buildings_se| serde_json::from_str(&buildings_se).map_err(|e| e.to_string()));
+        let available_buildings = buildings_de.unwrap_or_else(|e| {
+            println!("Error reading town available buildings: {}", &e);
+            HashMap::new()
+        });
         //
         TownInfoProvider {
             town_shareds: towns_de,
             town_specs: specs_de,
-            town_building_schemes: schemes_de
+            town_building_schemes: schemes,
+            schemes_error: schemes_error,
+            available_buildings: available_buildings
         }
     }
     /// Returns town's type based on its shared string.
     pub fn get_town_type(&self, shared: &String) -> Option<&TownType> {
         self.town_shareds.get(shared)
     }
+    /// Returns town's shared string based on its type.
+    pub fn get_town_shared(&self, town: &TownType) -> Option<&String> {
+        self.town_shareds.iter()
+            .find(|t| *t.1 == *town)
+            .map(|t| t.0)
+    }
     /// Returns town's in-game name based on its specialization string.
     pub fn get_town_name(&self, spec: &String) -> Option<&String> {
         self.town_specs.get(spec)
     }
+
+    /// Returns specialization town of given type gets when its type is changed.
+    /// It is the first(by path) random specialization of type from town_specs.json.
+    pub fn get_default_specialization(&self, town: &TownType) -> Option<&String> {
+        let town_constant = serde_json::to_value(town).ok()?.as_str()?.to_string();
+        // folders of specializations are named after factions, not after town types
+        let folder = match town_constant.as_str() {
+            "TOWN_HEAVEN" => "haven".to_string(),
+            "TOWN_NECROMANCY" => "necropolis".to_string(),
+            _=> town_constant.trim_start_matches("TOWN_").to_lowercase()
+        };
+        let prefix = format!("gamemechanics/townspecialization/{}/random/", folder);
+        self.town_specs.keys()
+            .filter(|spec| spec.starts_with(&prefix))
+            .min()
+    }
+
+    /// Removes buildings town can't have after its type is changed to given one.
+    /// Special buildings mean different things for different town types, so they are always removed, schemes set new ones.
+    pub fn remap_buildings(&self, buildings: &mut Vec<TownBuilding>, town: &TownType) {
+        let available = self.available_buildings.get(town);
+        buildings.retain(|building| {
+            let is_special = serde_json::to_value(&building.Type).ok()
+                .and_then(|t| t.as_str().map(|t| t.starts_with("TB_SPECIAL")))
+                .unwrap_or(false);
+            is_special == false && available.map_or(true, |a| a.contains(&building.Type))
+        });
+    }
+
+    /// Applies all schemes suitable for town in order of their priorities.
+    pub fn apply_schemes(&self, buildings: &mut Vec<TownBuilding>, map: &Map, modes: &Vec<TemplateModeName>, town: &TownType, owner: &PlayerID) {
+        let mut schemes: Vec<&TownBuildingScheme> = self.town_building_schemes.values()
+            .filter(|scheme| scheme.can_be_applied(map, modes, town, owner))
+            .collect();
+        schemes.sort_by_key(|scheme| scheme.priority);
+        let mut applied_priorities = vec![];
+        for scheme in schemes {
+            scheme.apply(buildings, &mut applied_priorities);
+        }
+    }
+
+    /// Returns problems found in building schemes.
+    pub fn validate_schemes(&self) -> Vec<String> {
+        match &self.schemes_error {
+            Some(e) => vec![format!("Schemes can't be read: {}", e)],
+            None => town_scheme::validate_schemes(&self.town_building_schemes, &self.available_buildings)
+        }
+    }
+
+    /// Returns buildings town of given type will have after all schemes are applied(map's own buildings are not counted).
+    pub fn preview_buildings(&self, map: &Map, modes: &Vec<TemplateModeName>, town: &TownType, owner: &PlayerID) -> Vec<SchemedTownBuilding> {
+        let mut buildings = vec![];
+        self.apply_schemes(&mut buildings, map, modes, town, owner);
+        buildings.iter()
+            .map(|b| SchemedTownBuilding { 
+                Type: b.Type, 
+                InitialUpgrade: b.InitialUpgrade, 
+                MaxUpgrade: b.MaxUpgrade 
+            })
+            .collect()
+    }
 }
 
 /// Provides town related information that can be shared between other patch groups.
@@ -78,7 +173,7 @@ pub struct NeutralTownCrossPatchInfo {
 pub struct TownPatchesGroup<'a> {
     patches: Vec<&'a mut dyn PatchModifyable<Modifyable = AdvMapTown>>,
     getters: Vec<&'a mut dyn PatchGetter<Patchable = AdvMapTown, Additional = TownGameInfo>>,
-    lua_strings: Vec<String>
+    towns_table: LuaTable
 }
 
 impl<'a> TownPatchesGroup<'a> {
@@ -86,7 +181,7 @@ impl<'a> TownPatchesGroup<'a> {
         TownPatchesGroup { 
             patches: vec![],
             getters: vec![],
-            lua_strings: vec![]
+            towns_table: LuaTable::new()
         }
     }
 
@@ -110,18 +205,18 @@ impl<'a> PatchGroup for TownPatchesGroup<'a> {
                 let mut town_game_info = TownGameInfo {
                     active_tile: Point {x: 0, y: 0}
                 };
-                for getter in self.getters.iter_mut() {
-                    getter.try_get(&town, &mut town_game_info);
-                }     
+                // patches go first cause they can change type of town.
                 for patch in self.patches.iter_mut() {
                     patch.try_modify(&mut town);
                 }
-                self.lua_strings.push(format!(
-                    "\t[\"{}\"] = {{rot = {}, x = {}, y = {}}},\n", 
-                    &town.name, town.rot, 
-                    town_game_info.active_tile.x, 
-                    town_game_info.active_tile.y
-                ));
+                for getter in self.getters.iter_mut() {
+                    getter.try_get(&town, &mut town_game_info);
+                }     
+                self.towns_table.insert(LuaKey::String(town.name.clone()), LuaTable::new()
+                    .with_field("rot", town.rot)
+                    .with_field("x", town_game_info.active_tile.x)
+                    .with_field("y", town_game_info.active_tile.y)
+                );
                 writer.write_serializable("AdvMapTown", &town).unwrap();
             },
             Err(e) => {
@@ -133,12 +228,15 @@ impl<'a> PatchGroup for TownPatchesGroup<'a> {
 
 impl<'a> GenerateLuaCode for TownPatchesGroup<'a>  {
     fn to_lua(&self, path: &PathBuf) {
-        let mut towns_info_output = "BTD_Towns = {\n".to_string();
-        for s in self.lua_strings.iter() {
-            towns_info_output += &s;
+        LuaChunk::new()
+            .with("BTD_Towns", self.towns_table.clone())
+            .write(path, "towns_info.lua");
+    }
+
+    fn generated_tables(&self) -> Vec<&'static str> {
+        match self.towns_table.is_empty() {
+            true => vec![],
+            false => vec!["BTD_Towns"]
         }
-        towns_info_output.push_str("}");
-        let mut file = std::fs::File::create(path.join("towns_info.lua")).unwrap();
-        file.write_all(&mut towns_info_output.as_bytes()).unwrap();       
     }
 }
\ No newline at end of file
@@ -1,6 +1,8 @@
-use homm5_types::{town::AdvMapTown, player::PlayerID};
+use std::collections::HashMap;
+use homm5_types::{town::AdvMapTown, hero::AdvMapHero, player::PlayerID, common::FileRef};
+use strum::IntoEnumIterator;
 
-use crate::{patch_strategy::PatchModifyable, map::template::TemplateModeName};
+use crate::{patch_strategy::{PatchModifyable, player::PlayerStartInfo}, map::{Map, template::TemplateModeName}};
 
 use super::TownInfoProvider;
 
@@ -39,58 +41,132 @@ impl PatchModifyable for TownNameApplier {
     }
 }
 
-/// Applies default schemes to town's buildings.
-pub struct DefaultTownSchemesApplier<'a> {
+/// Changes type of player's starting town if race was forced for this player.
+/// Town gets default specialization of new type and loses buildings new type can't have.
+/// Must be applied before schemes, so buildings will be set for the new town type.
+pub struct PlayerRaceApplier<'a> {
     town_info_provider: &'a TownInfoProvider,
-    map_modes: &'a Vec<TemplateModeName>
+    start_info: &'a HashMap<usize, PlayerStartInfo>,
+    /// numbers of starting towns among towns of their owners.
+    starting_towns: HashMap<PlayerID, usize>,
+    towns_count: HashMap<PlayerID, usize>
 }
 
-impl<'a> DefaultTownSchemesApplier<'a> {
-    pub fn new(provider: &'a TownInfoProvider, modes: &'a Vec<TemplateModeName>) -> Self {
-        DefaultTownSchemesApplier { 
-            town_info_provider: provider, 
-            map_modes: modes 
+impl<'a> PlayerRaceApplier<'a> {
+    pub fn new(provider: &'a TownInfoProvider, map: &'a Map) -> Self {
+        PlayerRaceApplier {
+            town_info_provider: provider,
+            start_info: &map.players_start_info,
+            starting_towns: Self::detect_starting_towns(map),
+            towns_count: HashMap::new()
+        }
+    }
+
+    /// Starting town of player is the nearest one to player's first hero(generator places starting hero near the town).
+    /// First town of player is used if player has no heroes.
+    fn detect_starting_towns(map: &Map) -> HashMap<PlayerID, usize> {
+        let heroes = map.read_objects::<AdvMapHero>("AdvMapHero").unwrap_or_else(|e| {
+            println!("Error reading heroes to detect starting towns: {}", &e);
+            vec![]
+        });
+        let towns = map.read_objects::<AdvMapTown>("AdvMapTown").unwrap_or_else(|e| {
+            println!("Error reading towns to detect starting towns: {}", &e);
+            vec![]
+        });
+        let mut starting_towns = HashMap::new();
+        for player in PlayerID::iter().filter(|p| *p != PlayerID::PlayerNone) {
+            let player_towns: Vec<&AdvMapTown> = towns.iter().filter(|t| t.player_id == player).collect();
+            if player_towns.is_empty() {
+                continue;
+            }
+            let starting_town = match heroes.iter().find(|h| h.player_id == player) {
+                Some(hero) => {
+                    let distance = |town: &AdvMapTown| {
+                        let dx = town.pos.x as f32 - hero.pos.x as f32;
+                        let dy = town.pos.y as f32 - hero.pos.y as f32;
+                        dx * dx + dy * dy
+                    };
+                    (0..player_towns.len())
+                        .min_by(|first, second| distance(player_towns[*first]).total_cmp(&distance(player_towns[*second])))
+                        .unwrap()
+                },
+                None => 0
+            };
+            starting_towns.insert(player, starting_town);
         }
+        starting_towns
     }
 }
 
-impl<'a> PatchModifyable for DefaultTownSchemesApplier<'a> {
+impl<'a> PatchModifyable for PlayerRaceApplier<'a> {
     type Modifyable = AdvMapTown;
 
     fn try_modify(&mut self, object: &mut Self::Modifyable) {
-        let no_xpointer_shared = object.shared.href.as_ref().unwrap().replace("#xpointer(/AdvMapTownShared)", "");
-        if let Some(town_type) = self.town_info_provider.get_town_type(&no_xpointer_shared) {
-            for scheme in self.town_info_provider.town_building_schemes.iter() {
-                if scheme.1.can_be_applied(self.map_modes, &town_type) == true {
-                    scheme.1.apply(&mut object.buildings.items);
-                }
+        if object.player_id == PlayerID::PlayerNone {
+            return;
+        }
+        let town_number = self.towns_count.entry(object.player_id.clone()).or_insert(0);
+        *town_number += 1;
+        if self.starting_towns.get(&object.player_id).is_some_and(|t| *t + 1 == *town_number) == false {
+            return;
+        }
+        let player_number = PlayerID::iter().position(|p| p == object.player_id).unwrap();
+        let race = self.start_info.get(&player_number).and_then(|i| i.race);
+        if let Some(actual_race) = race {
+            let no_xpointer_shared = object.shared.href.as_ref().unwrap().replace("#xpointer(/AdvMapTownShared)", "");
+            if self.town_info_provider.get_town_type(&no_xpointer_shared) == Some(&actual_race) {
+                return;
             }
-        };
+            match self.town_info_provider.get_town_shared(&actual_race) {
+                Some(shared) => {
+                    object.shared = FileRef {
+                        href: Some(format!("{}#xpointer(/AdvMapTownShared)", shared))
+                    };
+                    // specialization of previous town type can't be used
+                    object.specialization = FileRef {
+                        href: self.town_info_provider.get_default_specialization(&actual_race)
+                            .map(|spec| format!("/{}#xpointer(/TownSpecialization)", spec))
+                            .or(Some(String::new()))
+                    };
+                    self.town_info_provider.remap_buildings(&mut object.buildings.items, &actual_race);
+                },
+                None => println!("Impossible to find shared of town type {:?}", actual_race)
+            }
+        }
     }
 }
 
-/// Applies town scheme enableable by disable_neutral_towns_dwells setting.
-pub struct NeutralTownDwellingsDisabler<'a> {
-    can_be_applied: bool,
-    town_info_provider: &'a TownInfoProvider
+/// Applies default schemes to town's buildings.
+/// Schemes are applied in order of their priorities.
+pub struct DefaultTownSchemesApplier<'a> {
+    town_info_provider: &'a TownInfoProvider,
+    map: &'a Map,
+    map_modes: Vec<TemplateModeName>
 }
 
-impl<'a> NeutralTownDwellingsDisabler<'a> {
-    pub fn new(enabled: bool, tip: &'a TownInfoProvider) -> Self {
-        NeutralTownDwellingsDisabler {
-            can_be_applied: enabled,
-            town_info_provider: tip
+impl<'a> DefaultTownSchemesApplier<'a> {
+    pub fn new(provider: &'a TownInfoProvider, map: &'a Map) -> Self {
+        DefaultTownSchemesApplier { 
+            town_info_provider: provider, 
+            map: map,
+            map_modes: map.modes.keys().cloned().collect()
         }
     }
 }
 
-impl<'a> PatchModifyable for NeutralTownDwellingsDisabler<'a> {
+impl<'a> PatchModifyable for DefaultTownSchemesApplier<'a> {
     type Modifyable = AdvMapTown;
 
     fn try_modify(&mut self, object: &mut Self::Modifyable) {
-        if self.can_be_applied && object.player_id == PlayerID::PlayerNone {
-            let scheme = self.town_info_provider.town_building_schemes.get("neutral_town_dwells_disabled").unwrap();
-            scheme.apply(&mut object.buildings.items);
-        }       
+        let no_xpointer_shared = object.shared.href.as_ref().unwrap().replace("#xpointer(/AdvMapTownShared)", "");
+        if let Some(town_type) = self.town_info_provider.get_town_type(&no_xpointer_shared) {
+            self.town_info_provider.apply_schemes(
+                &mut object.buildings.items, 
+                self.map, 
+                &self.map_modes, 
+                town_type, 
+                &object.player_id
+            );
+        };
     }
-}
\ No newline at end of file
+}
@@ -1,14 +1,17 @@
-use homm5_types::town::{TownType, TownBuilding, TownBuildingType, TownBuildingLevel};
+use std::collections::HashMap;
+use homm5_types::{town::{TownType, TownBuilding, TownBuildingType, TownBuildingLevel}, player::PlayerID};
 use serde::{Serialize, Deserialize};
+use strum::IntoEnumIterator;
 
-use crate::map::template::TemplateModeName;
+use crate::map::{Map, MapSettingFlag, template::TemplateModeName};
 
 /// This mod presents TownBuildingScheme a preconfigured list of buildings 
 /// that can be applied to the town if map has needed template and town has needed type
+/// and both map and town match scheme's conditions.
 
 /// A copy of homm5_types::town::TownBuilding. 
 /// I need this to deserialize from json, cause that stupid thing "#[serde(with = "quick_xml::serde_helpers::text_content")]" totally breaks it.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(non_snake_case)]
 pub struct SchemedTownBuilding {
     pub Type: TownBuildingType,
@@ -16,28 +19,96 @@ pub struct SchemedTownBuilding {
     pub MaxUpgrade: TownBuildingLevel
 }
 
+/// Owner of town scheme can be applied to.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum TownOwnerCondition {
+    Neutral,
+    AnyPlayer,
+    Player(PlayerID)
+}
+
+/// Additional conditions of scheme. Every None condition is always true.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct TownSchemeConditions {
+    owner: Option<TownOwnerCondition>,
+    /// sizes of map in tiles.
+    map_sizes: Option<Vec<usize>>,
+    /// teams of town's owner, neutral towns never match it.
+    teams: Option<Vec<usize>>,
+    /// all of these settings must be set for map.
+    settings: Option<Vec<MapSettingFlag>>
+}
+
+impl TownSchemeConditions {
+    fn check(&self, map: &Map, owner: &PlayerID) -> bool {
+        let owner_matches = match &self.owner {
+            None => true,
+            Some(TownOwnerCondition::Neutral) => *owner == PlayerID::PlayerNone,
+            Some(TownOwnerCondition::AnyPlayer) => *owner != PlayerID::PlayerNone,
+            Some(TownOwnerCondition::Player(player)) => *owner == *player
+        };
+        let team_matches = match &self.teams {
+            None => true,
+            Some(teams) => {
+                // player's number is same as index of teams info
+                *owner != PlayerID::PlayerNone &&
+                    PlayerID::iter().position(|p| p == *owner)
+                        .and_then(|number| map.teams_info.get(number))
+                        .is_some_and(|team| teams.contains(team))
+            }
+        };
+        owner_matches && team_matches &&
+            (self.map_sizes.is_none() || self.map_sizes.as_ref().unwrap().contains(&map.size)) &&
+            (self.settings.is_none() || self.settings.as_ref().unwrap().iter().all(|s| map.settings.is_set(s)))
+    }
+}
+
 /// If templates is None, scheme can be applied to any template as well as for any town if town_types is None.
+/// When several schemes touch the same building, scheme with higher priority overrides its levels,
+/// levels of schemes with equal priority are merged.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TownBuildingScheme {
     buildings: Vec<SchemedTownBuilding>,
     modes: Option<Vec<TemplateModeName>>,
-    town_types: Option<Vec<TownType>>
+    town_types: Option<Vec<TownType>>,
+    #[serde(default)]
+    conditions: TownSchemeConditions,
+    #[serde(default)]
+    pub priority: i32
 }
 
 impl TownBuildingScheme {
-    /// Checks if scheme can be applied to the concrete town.
-    pub fn can_be_applied<'a>(&self, modes: &Vec<TemplateModeName>, town: &TownType) -> bool {
+    /// Checks if scheme can be applied to the concrete town. Modes are passed separately to make previews possible.
+    pub fn can_be_applied(&self, map: &Map, modes: &Vec<TemplateModeName>, town: &TownType, owner: &PlayerID) -> bool {
         return (self.modes.is_none() || self.modes.as_ref().unwrap().iter().any(|m| modes.contains(m))) &&
-            (self.town_types.is_none() || self.town_types.as_ref().unwrap().iter().any(|t | *t == *town))
+            (self.town_types.is_none() || self.town_types.as_ref().unwrap().iter().any(|t | *t == *town)) &&
+            self.conditions.check(map, owner)
     }
 
     /// This is stupid but i need both xml and json se/de so i need this type conversion here.
-    pub fn apply(&self, town_builds: &mut Vec<TownBuilding>) {
+    /// applied_priorities keeps priority of scheme that changed building last time.
+    pub fn apply(&self, town_builds: &mut Vec<TownBuilding>, applied_priorities: &mut Vec<(TownBuildingType, i32)>) {
         for build in self.buildings.iter() {
+            let previous_priority = applied_priorities.iter_mut().find(|p| p.0 == build.Type);
+            let is_overriding = match previous_priority {
+                Some(priority) => {
+                    let is_higher = priority.1 < self.priority;
+                    priority.1 = self.priority;
+                    is_higher
+                },
+                None => {
+                    applied_priorities.push((build.Type, self.priority));
+                    false
+                }
+            };
             // check if building already in town
             let current_building = town_builds.iter_mut()
                 .find(|b| b.Type == build.Type);
             match current_building {
+                Some(building) if is_overriding == true => {
+                    building.InitialUpgrade = build.InitialUpgrade;
+                    building.MaxUpgrade = build.MaxUpgrade;
+                },
                 Some(building) => {
                     if build.InitialUpgrade == TownBuildingLevel::BldUpgNone {
                         building.InitialUpgrade = TownBuildingLevel::BldUpgNone
@@ -65,4 +136,92 @@ impl TownBuildingScheme {
             }
         }
     }
-}
\ No newline at end of file
+}
+/// Name of scheme that was applied by NeutralTownDwellingsDisabler before schemes got conditions.
+const NEUTRAL_DWELLS_SCHEME: &str = "neutral_town_dwells_disabled";
+
+/// Schemes files written before conditions existed have no conditions for neutral dwellings scheme,
+/// so it would be applied to every town. Conditions it had by design are set for it here.
+pub fn migrate_schemes(schemes: &mut HashMap<String, TownBuildingScheme>) {
+    if let Some(scheme) = schemes.get_mut(NEUTRAL_DWELLS_SCHEME) {
+        if scheme.conditions.owner.is_none() && scheme.conditions.settings.is_none() {
+            scheme.conditions.owner = Some(TownOwnerCondition::Neutral);
+            scheme.conditions.settings = Some(vec![MapSettingFlag::DisableNeutralTownsDwells]);
+        }
+    }
+}
+
+/// Checks every scheme for impossible levels and buildings that town types don't have,
+/// also reports buildings that are changed differently by schemes that can be applied to the same towns with equal priority.
+/// available_buildings maps town types to building types they have.
+pub fn validate_schemes(
+    schemes: &HashMap<String, TownBuildingScheme>, 
+    available_buildings: &HashMap<TownType, Vec<TownBuildingType>>
+) -> Vec<String> {
+    let mut issues = vec![];
+    for scheme in schemes.iter() {
+        let town_types: Vec<&TownType> = match &scheme.1.town_types {
+            Some(types) => types.iter().collect(),
+            None => available_buildings.keys().collect()
+        };
+        for build in scheme.1.buildings.iter() {
+            if build.MaxUpgrade != TownBuildingLevel::BldUpgNone && build.InitialUpgrade > build.MaxUpgrade {
+                issues.push(format!(
+                    "Scheme {}: initial upgrade {:?} of {:?} is above max upgrade {:?}", 
+                    scheme.0, &build.InitialUpgrade, &build.Type, &build.MaxUpgrade
+                ));
+            }
+            for town_type in town_types.iter() {
+                match available_buildings.get(town_type) {
+                    Some(buildings) => {
+                        if buildings.contains(&build.Type) == false {
+                            issues.push(format!("Scheme {}: town {:?} has no building {:?}", scheme.0, town_type, &build.Type));
+                        }
+                    },
+                    None => issues.push(format!("Scheme {}: no buildings info for town {:?}", scheme.0, town_type))
+                }
+            }
+        }
+        // conflicts with other schemes, every pair is checked once
+        for other in schemes.iter().filter(|other| other.0 > scheme.0) {
+            if scheme.1.priority != other.1.priority || scheme.1.may_intersect(other.1) == false {
+                continue;
+            }
+            for build in scheme.1.buildings.iter() {
+                let conflicting = other.1.buildings.iter().find(|b| {
+                    b.Type == build.Type && (b.InitialUpgrade != build.InitialUpgrade || b.MaxUpgrade != build.MaxUpgrade)
+                });
+                if conflicting.is_some() {
+                    issues.push(format!(
+                        "Schemes {} and {} with priority {} set different levels of {:?}, they will be merged", 
+                        scheme.0, other.0, scheme.1.priority, &build.Type
+                    ));
+                }
+            }
+        }
+    }
+    issues
+}
+
+impl TownBuildingScheme {
+    /// Checks if schemes can be applied to the same town(only modes, town types and owners are considered).
+    fn may_intersect(&self, other: &TownBuildingScheme) -> bool {
+        let modes_intersect = match (&self.modes, &other.modes) {
+            (Some(modes), Some(other_modes)) => modes.iter().any(|m| other_modes.contains(m)),
+            _=> true
+        };
+        let towns_intersect = match (&self.town_types, &other.town_types) {
+            (Some(towns), Some(other_towns)) => towns.iter().any(|t| other_towns.contains(t)),
+            _=> true
+        };
+        let owners_intersect = match (&self.conditions.owner, &other.conditions.owner) {
+            (Some(TownOwnerCondition::Neutral), Some(TownOwnerCondition::AnyPlayer)) |
+            (Some(TownOwnerCondition::AnyPlayer), Some(TownOwnerCondition::Neutral)) |
+            (Some(TownOwnerCondition::Neutral), Some(TownOwnerCondition::Player(_))) |
+            (Some(TownOwnerCondition::Player(_)), Some(TownOwnerCondition::Neutral)) => false,
+            (Some(TownOwnerCondition::Player(player)), Some(TownOwnerCondition::Player(other_player))) => *player == *other_player,
+            _=> true
+        };
+        modes_intersect && towns_intersect && owners_intersect
+    }
+}
@@ -1,9 +1,11 @@
 pub mod modifiers;
 pub mod getters;
 
-use std::{path::PathBuf, io::Write, collections::HashMap};
+use std::{path::PathBuf, collections::HashMap};
 use homm5_types::treasure::AdvMapTreasure;
+use serde::{Serialize, Deserialize};
 use self::getters::TreasureGameInfo;
+use crate::lua::{LuaTable, LuaKey, LuaValue, LuaChunk};
 use super::{PatchModifyable, GenerateLuaCode, PatchGetter, PatchGroup};
 
 #[derive(Debug, serde::Deserialize, serde::Serialize, strum_macros::EnumString, Hash, PartialEq, Eq, Clone, Copy)]
@@ -19,6 +21,26 @@ pub enum TreasureType {
     CAMPFIRE
 }
 
+/// Change applied to treasures of some type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TreasureRuleAction {
+    Multiply(f32),
+    Clamp {
+        min: u32,
+        max: u32
+    },
+    /// Treasure becomes treasure of other type with same amount.
+    ConvertTo(TreasureType),
+    Remove
+}
+
+/// Rules are applied in order they are listed, so converted treasure can be changed by next rules for its new type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreasureRule {
+    pub treasure_type: TreasureType,
+    pub action: TreasureRuleAction
+}
+
 /// Provides information that can be used across different patches in TownPatchesGroup
 pub struct TreasureInfoProvider {
     /// Maps treasure type to its xdb file
@@ -35,6 +57,11 @@ impl TreasureInfoProvider {
         }
     }
 
+    /// Returns xdb file of treasure type.
+    pub fn get_treasure_xdb(&self, treasure_type: &TreasureType) -> Option<&String> {
+        self.treasures_xdbs.get(treasure_type)
+    }
+
     /// Returns treasure type based on its shared string.
     pub fn get_treasure_type(&self, shared: &String) -> Option<&TreasureType> {
         if let Some(treasure_type) = self.treasures_xdbs.iter()
@@ -50,7 +77,8 @@ impl TreasureInfoProvider {
 pub struct TreasurePatchesGroup<'a> {
     patches: Vec<&'a mut dyn PatchModifyable<Modifyable = AdvMapTreasure>>,
     getters: Vec<&'a mut dyn PatchGetter<Patchable = AdvMapTreasure, Additional = TreasureGameInfo>>,
-    lua_strings: Vec<String>
+    treasures_table: LuaTable,
+    is_removed: bool
 }
 
 impl<'a> TreasurePatchesGroup<'a> {
@@ -58,7 +86,8 @@ impl<'a> TreasurePatchesGroup<'a> {
         TreasurePatchesGroup { 
             patches: vec![],
             getters: vec![],
-            lua_strings: vec![]
+            treasures_table: LuaTable::new(),
+            is_removed: false
         }
     }
 
@@ -81,33 +110,42 @@ impl<'a> PatchGroup for TreasurePatchesGroup<'a> {
                 let mut treasure_game_info = TreasureGameInfo{_type: TreasureType::CHEST, amount: 0};
                 for patch in self.patches.iter_mut() {
                     patch.try_modify(&mut treasure);
+                    if patch.is_removal_requested() == true {
+                        self.is_removed = true;
+                        return;
+                    }
                 }
                 for getter in self.getters.iter_mut() {
                     getter.try_get(&treasure, &mut treasure_game_info);
                 }
-                self.lua_strings.push(
-                    format!(
-                        "\t[\"{}\"] = {{type = TREASURE_{:?}, amount = {}}},\n",
-                        &treasure.name,
-                        treasure_game_info._type,
-                        treasure_game_info.amount
-                    )
+                self.treasures_table.insert(LuaKey::String(treasure.name.clone()), LuaTable::new()
+                    .with_field("type", LuaValue::Constant(format!("TREASURE_{:?}", treasure_game_info._type)))
+                    .with_field("amount", treasure_game_info.amount)
                 );
                 writer.write_serializable("AdvMapTreasure", &treasure).unwrap();
             }
             Err(e) => println!("Error deserializing treasure: {}", e.to_string())
         }
     }
+
+    fn take_removal(&mut self) -> bool {
+        let is_removed = self.is_removed;
+        self.is_removed = false;
+        is_removed
+    }
 }
 
 impl<'a> GenerateLuaCode for TreasurePatchesGroup<'a> {
     fn to_lua(&self, path: &PathBuf) {
-        let mut treasures_info_output = "BTD_Treasures = {\n".to_string();
-        for s in self.lua_strings.iter() {
-            treasures_info_output += &s;
+        LuaChunk::new()
+            .with("BTD_Treasures", self.treasures_table.clone())
+            .write(path, "treasures_info.lua");
+    }
+
+    fn generated_tables(&self) -> Vec<&'static str> {
+        match self.treasures_table.is_empty() {
+            true => vec![],
+            false => vec!["BTD_Treasures"]
         }
-        treasures_info_output.push_str("}");
-        let mut file = std::fs::File::create(path.join("treasures_info.lua")).unwrap();
-        file.write_all(&mut treasures_info_output.as_bytes()).unwrap();   
     }
 }
\ No newline at end of file
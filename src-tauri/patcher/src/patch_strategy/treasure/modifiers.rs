@@ -1,5 +1,8 @@
+use std::sync::RwLock;
 use homm5_types::treasure::AdvMapTreasure;
-use crate::patch_strategy::PatchModifyable;
+use serde::Serialize;
+use crate::{patch_strategy::PatchModifyable, report::PatchReport};
+use super::{TreasureInfoProvider, TreasureRule, TreasureRuleAction, TreasureType};
 
 /// Modifyable patch strategies for TreasurePatchesGroup.
 
@@ -23,4 +26,93 @@ impl PatchModifyable for TreasureNameApplier {
         self.treasure_count += 1;
         object.name = format!("Treasure_{}", self.treasure_count);
     }
-}
\ No newline at end of file
+}
+/// Treasure state before and after rebalancing.
+#[derive(Serialize)]
+struct TreasureRebalanceRecord {
+    treasure: String,
+    original_type: TreasureType,
+    original_amount: u32,
+    /// None if treasure was removed.
+    result: Option<(TreasureType, u32)>
+}
+
+/// Changes amounts and types of treasures by configured rules, every changed treasure is recorded in patch report.
+/// Must be applied after TreasureNameApplier to make report readable.
+pub struct TreasureRebalancer<'a> {
+    rules: &'a Vec<TreasureRule>,
+    treasure_info_provider: &'a TreasureInfoProvider,
+    report: &'a RwLock<PatchReport>,
+    removal_requested: bool
+}
+
+impl<'a> TreasureRebalancer<'a> {
+    pub fn new(rules: &'a Vec<TreasureRule>, tip: &'a TreasureInfoProvider, report: &'a RwLock<PatchReport>) -> Self {
+        TreasureRebalancer {
+            rules: rules,
+            treasure_info_provider: tip,
+            report: report,
+            removal_requested: false
+        }
+    }
+}
+
+impl<'a> PatchModifyable for TreasureRebalancer<'a> {
+    type Modifyable = AdvMapTreasure;
+
+    fn try_modify(&mut self, object: &mut Self::Modifyable) {
+        self.removal_requested = false;
+        let no_xpointer_shared = object.shared.href.as_ref().unwrap().replace("#xpointer(/AdvMapTreasureShared)", "");
+        let original_type = match self.treasure_info_provider.get_treasure_type(&no_xpointer_shared) {
+            Some(treasure_type) => *treasure_type,
+            None => return
+        };
+        let original_amount = object.amount;
+        let mut current_type = original_type;
+        let mut is_changed = false;
+        for rule in self.rules.iter() {
+            // after conversion rules of new type are applied
+            if rule.treasure_type != current_type {
+                continue;
+            }
+            is_changed = true;
+            match &rule.action {
+                TreasureRuleAction::Multiply(multiplier) => {
+                    object.amount = ((object.amount as f32 * multiplier).round() as u32).max(1);
+                },
+                TreasureRuleAction::Clamp { min, max } => {
+                    // not a clamp() call, cause it panics if min is greater than max
+                    object.amount = object.amount.max(*min).min(*max);
+                },
+                TreasureRuleAction::ConvertTo(new_type) => {
+                    match self.treasure_info_provider.get_treasure_xdb(new_type) {
+                        Some(xdb) => {
+                            object.shared.href = Some(format!("{}#xpointer(/AdvMapTreasureShared)", xdb));
+                            current_type = *new_type;
+                        },
+                        None => println!("No xdb found for treasure type {:?}", new_type)
+                    }
+                },
+                TreasureRuleAction::Remove => {
+                    self.removal_requested = true;
+                    break;
+                }
+            }
+        }
+        if is_changed == true {
+            self.report.write().unwrap().add("treasures", &TreasureRebalanceRecord {
+                treasure: object.name.clone(),
+                original_type: original_type,
+                original_amount: original_amount,
+                result: match self.removal_requested {
+                    true => None,
+                    false => Some((current_type, object.amount))
+                }
+            });
+        }
+    }
+
+    fn is_removal_requested(&self) -> bool {
+        self.removal_requested
+    }
+}
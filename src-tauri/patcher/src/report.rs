@@ -0,0 +1,55 @@
+//! Patch report collects information about changes patches made to map objects, 
+//! so organisers can check what was actually changed(original strengths of stacks for example).
+//! Report is shared between patch groups with RwLock and written into patch_report.json of map directory.
+
+use std::{collections::BTreeMap, path::PathBuf, io::Write, sync::RwLock};
+use serde::Serialize;
+
+use crate::patch_strategy::WriteAdditional;
+
+#[derive(Debug, Default, Serialize)]
+pub struct PatchReport {
+    /// Records by names of sections(every patch uses its own section).
+    sections: BTreeMap<String, Vec<serde_json::Value>>
+}
+
+impl PatchReport {
+    pub fn new() -> Self {
+        PatchReport { 
+            sections: BTreeMap::new() 
+        }
+    }
+
+    /// Adds record to given section.
+    pub fn add<T: Serialize>(&mut self, section: &str, record: &T) {
+        match serde_json::to_value(record) {
+            Ok(value) => {
+                self.sections.entry(section.to_string()).or_insert(vec![]).push(value);
+            },
+            Err(e) => println!("Error adding record to patch report section {}: {}", section, e.to_string())
+        }
+    }
+}
+
+/// Writes collected report with patched map.
+pub struct PatchReportWriter<'a> {
+    report: &'a RwLock<PatchReport>,
+    write_dir: &'a PathBuf
+}
+
+impl<'a> PatchReportWriter<'a> {
+    pub fn new(report: &'a RwLock<PatchReport>, dir: &'a PathBuf) -> Self {
+        PatchReportWriter {
+            report: report,
+            write_dir: dir
+        }
+    }
+}
+
+impl<'a> WriteAdditional for PatchReportWriter<'a> {
+    fn try_write(&self) {
+        let report = self.report.read().unwrap();
+        let mut file = std::fs::File::create(self.write_dir.join("patch_report.json")).unwrap();
+        file.write_all(serde_json::to_string_pretty(&*report).unwrap().as_bytes()).unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+/// Reading and writing of HoMM5 text files.
+/// Game's text files(*-text-0.txt for example) are UTF-16LE, usually with byte order mark.
+/// Text is kept exactly as it is in file, so line endings aren't changed and BOM is written back only if file had it.
+
+use std::path::PathBuf;
+
+const UTF16LE_BOM: [u8; 2] = [0xFF, 0xFE];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Utf16Text {
+    pub text: String,
+    pub has_bom: bool
+}
+
+impl Utf16Text {
+    /// New text is written with BOM, cause game's files have it.
+    pub fn new(text: String) -> Self {
+        Utf16Text {
+            text: text,
+            has_bom: true
+        }
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let (bytes, has_bom) = match bytes.strip_prefix(&UTF16LE_BOM) {
+            Some(no_bom_bytes) => (no_bom_bytes, true),
+            None => (bytes, false)
+        };
+        if bytes.len() % 2 != 0 {
+            return Err(format!("Odd number of bytes({}) in UTF-16 text", bytes.len()));
+        }
+        let units: Vec<u16> = bytes.chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        match String::from_utf16(&units) {
+            Ok(text) => Ok(Utf16Text { text: text, has_bom: has_bom }),
+            Err(e) => Err(e.to_string())
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.text.len() * 2 + 2);
+        if self.has_bom == true {
+            bytes.extend_from_slice(&UTF16LE_BOM);
+        }
+        for unit in self.text.encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn read(path: &PathBuf) -> Result<Self, String> {
+        std::fs::read(path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| Self::decode(&bytes))
+            .map_err(|e| format!("Error reading text file {:?}: {}", path, e))
+    }
+
+    pub fn write(&self, path: &PathBuf) -> Result<(), String> {
+        std::fs::write(path, self.encode())
+            .map_err(|e| format!("Error writing text file {:?}: {}", path, e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::Utf16Text;
+
+    fn test_data(file: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("data").join(file)
+    }
+
+    #[test]
+    fn map_text_files_round_trip() {
+        for file in ["mapname-text-0.txt", "mapdesc-text-0.txt"] {
+            let bytes = std::fs::read(test_data(file)).unwrap();
+            let text = Utf16Text::decode(&bytes).unwrap();
+            assert!(text.has_bom);
+            assert_eq!(text.encode(), bytes, "{} changed after round trip", file);
+        }
+    }
+
+    #[test]
+    fn line_endings_are_kept() {
+        let text = Utf16Text::read(&test_data("mapdesc-text-0.txt")).unwrap();
+        assert!(text.text.contains("\r\n"));
+        assert!(text.text.replace("\r\n", "").contains('\n') == false);
+        assert!(text.text.starts_with("Карта"));
+    }
+
+    #[test]
+    fn text_without_bom_is_written_without_it() {
+        let text = Utf16Text::decode(&[0x41, 0x00, 0x0D, 0x00, 0x0A, 0x00]).unwrap();
+        assert_eq!(text, Utf16Text { text: "A\r\n".to_string(), has_bom: false });
+        assert_eq!(text.encode(), vec![0x41, 0x00, 0x0D, 0x00, 0x0A, 0x00]);
+    }
+
+    #[test]
+    fn modified_text_is_written_back() {
+        let path = std::env::temp_dir().join("btd_patcher_mapname-text-0.txt");
+        let mut text = Utf16Text::read(&test_data("mapname-text-0.txt")).unwrap();
+        text.text = format!("BTD_{}", &text.text);
+        text.write(&path).unwrap();
+        let written = Utf16Text::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(written, text);
+    }
+
+    #[test]
+    fn broken_text_is_error() {
+        assert!(Utf16Text::decode(&[0xFF, 0xFE, 0x41]).is_err());
+        // unpaired surrogate
+        assert!(Utf16Text::decode(&[0x00, 0xD8]).is_err());
+    }
+}
@@ -4,6 +4,7 @@ use serde::{Serialize, Deserialize};
 use tauri::{State, AppHandle, Manager};
 use walkdir::WalkDir;
 
+use patcher::locale::Locale;
 use crate::file_management::{FileMoveType, PathManager};
 
 #[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
@@ -29,7 +30,24 @@ pub struct Documentation {
 #[derive(Serialize, Deserialize)]
 pub struct BaseSettings {
     #[serde(rename = "CurrentGameMode")]
-    pub current_mode: GameMode
+    pub current_mode: GameMode,
+    /// Locale of launcher and patcher output.
+    #[serde(rename = "Locale", default)]
+    pub locale: Locale
+}
+
+impl BaseSettings {
+    pub fn read(path_manager: &PathManager) -> Self {
+        let settings_info = std::fs::read_to_string(path_manager.cfg().join("base.json")).unwrap();
+        serde_json::from_str(&settings_info).unwrap()
+    }
+
+    pub fn write(&self, path_manager: &PathManager) {
+        let base_path = path_manager.cfg().join("base.json");
+        std::fs::remove_file(&base_path).unwrap();
+        let mut file = std::fs::File::create(&base_path).unwrap();
+        file.write_all(serde_json::to_string_pretty(self).unwrap().as_bytes()).unwrap();
+    }
 }
 
 pub struct GameModeManager {
@@ -41,11 +59,9 @@ pub struct GameModeManager {
 
 impl GameModeManager {
     pub fn new(path_manager: &PathManager) -> Self {
-        let current_mode_info = std::fs::read_to_string(path_manager.cfg().join("base.json")).unwrap();
         let file_movement_info = std::fs::read_to_string(path_manager.cfg().join("modes\\file_move.json")).unwrap();
         let docs_info = std::fs::read_to_string(path_manager.cfg().join("modes\\docs.json")).unwrap();
-        let settings: BaseSettings = serde_json::from_str(&current_mode_info)
-            .unwrap();
+        let settings = BaseSettings::read(path_manager);
         let mut file_movement: HashMap<GameMode, Vec<FileMovementInfo>> = serde_json::from_str(&file_movement_info)
             .unwrap();
         let docs = serde_json::from_str(&docs_info)
@@ -147,11 +163,9 @@ pub async fn switch_mode(
     path_manager: State<'_, PathManager>, 
     new_mode: GameMode
 ) -> Result<(), ()> {
-    let new_settings = BaseSettings{current_mode: new_mode.clone()};
-    let base_path = path_manager.cfg().join("base.json");
-    std::fs::remove_file(&base_path).unwrap();
-    let mut file = std::fs::File::create(&base_path).unwrap();
-    file.write_all(serde_json::to_string_pretty(&new_settings).unwrap().as_bytes()).unwrap();
+    let mut new_settings = BaseSettings::read(&path_manager);
+    new_settings.current_mode = new_mode.clone();
+    new_settings.write(&path_manager);
     //
     let mut current_mode = game_mode_manager.current_mode.lock().await;
     game_mode_manager.remove_files(&current_mode, &path_manager).await;
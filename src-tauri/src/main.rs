@@ -19,6 +19,7 @@ use tauri::{State, Manager};
 use tokio::sync::{mpsc, Mutex};
 
 use file_management::PathManager;
+use text::LocalizationManager;
 
 use patcher::{Patcher};
 
@@ -49,6 +50,7 @@ async fn main() {
     let game_mode_manager = GameModeManager::new(&path_manager);
     let downloader = Downloader::new();
     let patcher_manager = PatcherManager::new(path_manager.cfg());
+    let localization_manager = LocalizationManager::new(&path_manager);
     let database_manager = DatabaseManager {
         pool: sqlx::SqlitePool::connect(path_manager.cfg().join("update\\local.db").to_str().unwrap()).await.unwrap()
     };
@@ -59,11 +61,36 @@ async fn main() {
         .manage(downloader)
         .manage(database_manager)
         .manage(patcher_manager)
+        .manage(localization_manager)
         .invoke_handler(tauri::generate_handler![
             patch_management::pick_map,
             patch_management::unpack_map,
             patch_management::update_player_team_info,
+            patch_management::update_player_control_info,
+            patch_management::update_stacks_scaling,
+            patch_management::update_treasure_rules,
+            patch_management::set_portals_repair_policy,
+            patch_management::set_patch_seed,
+            patch_management::update_moon_calendar,
+            patch_management::preview_moon_calendar,
+            patch_management::update_artifacts_bans,
+            patch_management::get_base_artifacts,
+            patch_management::update_mechanics_bans,
+            patch_management::get_base_spells,
+            patch_management::get_bans_presets,
+            patch_management::update_economy_rules,
+            patch_management::validate_town_schemes,
+            patch_management::preview_town_buildings,
+            patch_management::update_player_handicap,
+            patch_management::remove_player_handicap,
+            patch_management::set_player_race,
+            patch_management::set_player_starting_hero,
+            patch_management::start_hero_draft,
+            patch_management::make_draft_step,
+            patch_management::get_draft_pools,
+            patch_management::cancel_hero_draft,
             patch_management::set_night_lights_setting,
+            patch_management::update_lights_selection,
             patch_management::set_weeks_only_setting,
             patch_management::set_neutral_towns_dwells_setting,
             patch_management::set_enable_new_arts_setting,
@@ -72,6 +99,9 @@ async fn main() {
             patch_management::add_final_battle_mode,
             patch_management::add_capture_object_mode,
             patch_management::add_economic_mode,
+            patch_management::get_mode_definitions,
+            patch_management::add_custom_mode,
+            patch_management::remove_custom_mode,
             patch_management::patch_map,
             startup::start_game,
             startup::start_telegram_dialog,
@@ -83,7 +113,10 @@ async fn main() {
             game_mode::show_manual,
             game_mode::show_wheel,
             game_mode::switch_mode,
-            scan_management::scan_files
+            scan_management::scan_files,
+            text::set_locale,
+            text::get_locale_strings,
+            text::get_missing_locale_keys
         ])
         .setup(|app|{
             let main_window = app.get_window("main").unwrap();
@@ -1,39 +1,56 @@
-use homm5_types::town;
+use homm5_types::{town, player::PlayerID, building::AdvMapBuilding, treasure::AdvMapTreasure, creature::AdvMapMonster, hero::AdvMapHero};
 use tauri::{Manager, State, AppHandle, api::dialog::FileDialogBuilder, App};
 use patcher::{Patcher,
     map::{Unpacker, Map, template::{Template, TemplateTransferable, TemplatesInfoModel, TemplateModeType, TemplateModeName}}, 
     patch_strategy::{
         PatchGroup,
         base::{MapScriptCreator, CustomTeamsCreator, RMGmapRemover, MapNameChanger}, 
-        building::{BuildingInfoProvider, BuildingPatchesGroup, modifiers::{BuildingNameApplier, OutcastTavernReplacer}, getters::BuildingTypeDetector}, 
-        treasure::{TreasureInfoProvider, TreasurePatchesGroup, modifiers::TreasureNameApplier, getters::TreasurePropsDetector}, 
-        player::{PlayersInfoProvider, PlayersCrossPatchInfo, PlayerPatchesGroup, modifiers::{PlayerTeamSelector, OutcastPlayerHeroSelector, InactivePlayersTavernFilterRemover}, TeamsGenerator}, 
-        light::{LightsInfoProvider, AmbientLightCreator, GroundAmbientLightsCreator}, 
-        quest::{QuestInfoProvider, QuestPatchesGroup, modifiers::{MapInitQuestCreator, MapModesQuestCreator, QuestEmptyItemsFixer}},
+        building::{BuildingInfoProvider, BuildingPatchesGroup, modifiers::BuildingNameApplier, getters::BuildingTypeDetector, 
+            portals::{PortalsAnalysis, PortalsRepairer, PortalsRepairPolicy}
+        }, 
+        treasure::{TreasureInfoProvider, TreasurePatchesGroup, TreasureRule, modifiers::{TreasureNameApplier, TreasureRebalancer}, getters::TreasurePropsDetector}, 
+        player::{PlayersInfoProvider, PlayersCrossPatchInfo, PlayerPatchesGroup, TeamsGenerator, PlayersInfoGenerator, StartingHero, PlayerHandicap, PlayerControl, PlayerControlInfo,
+            modifiers::{PlayerTeamSelector, OutcastPlayerHeroSelector, DraftHeroSelector, StartingHeroSelector, PlayerHandicapApplier, PlayerControlApplier, InactivePlayersTavernFilterRemover},
+            draft::{HeroDraft, DraftStep, DraftLogWriter, known_players_races}
+        }, 
+        light::{LightsInfoProvider, AmbientLightCreator, GroundAmbientLightsCreator, LightsSelection}, 
+        quest::{QuestInfoProvider, QuestPatchesGroup, objectives::ObjectivesProvider, modifiers::{MapInitQuestCreator, ModesObjectivesCreator, QuestEmptyItemsFixer}},
         town::{TownInfoProvider, TownPatchesGroup, 
-            modifiers::{TownNameApplier, DefaultTownSchemesApplier, NeutralTownDwellingsDisabler}, 
+            modifiers::{TownNameApplier, PlayerRaceApplier, DefaultTownSchemesApplier}, 
             getters::{TownActiveTilesDetector, PlayerRaceDetector, CapturableTownDetector}, 
+            town_scheme::SchemedTownBuilding,
             PlayerRaceCrossPatchInfo, NeutralTownCrossPatchInfo
         }, 
         modes::{
-            final_battle::{FinalBattleTime, FinalBattleModeTextProcessor}, 
-            economic::{ResourceWinInfo, EconomicModeTextProcessor}, 
-            capture::CaptureObjectModeTextProcessor,
-            outcast::{OutcastMechanicsWriter, OutcastTextWriter, AvailableHeroesWriter}, ModesInfoGenerator
+            final_battle::FinalBattleTime, 
+            economic::ResourceWinInfo, 
+            text::{TemplateContext, ModeTextProcessor},
+            outcast::{OutcastMechanicsWriter, OutcastTextWriter, AvailableHeroesWriter}, ModesInfoGenerator,
+            definition::{ModeDefinitionsProvider, ModeDefinition, ModeStrategy, ModeParams}
         }, 
-        creature::{CreaturePatchesGroup, modifiers::{CreatureNameApplier, AdditionalStackFixer}},
+        creature::{CreaturePatchesGroup, CreatureInfoProvider, StacksScaling, StackScalingRule, modifiers::{CreatureNameApplier, AdditionalStackFixer, NeutralStackScaler}},
         terrain::{UndergroundTerrainCreator, UndergroundEnabler, UndergroundTerrainNameApplier},
-        objects::CommonObjectsCreator, mechanics::{MoonCalendarWriter, NewArtifactsEnabler}
-    }, CodeGenerator, FileWriter, TextProcessor
+        substitution::{SubstitutionsProvider, ObjectSubstitutor},
+        economy::{EconomyInfoProvider, EconomyRule, MinePatchesGroup, DwellingPatchesGroup, 
+            modifiers::{MineNameApplier, DwellingNameApplier, MineOwnerApplier, DwellingOwnerApplier}
+        },
+        hero::{HeroInfoProvider, HeroPatchesGroup, modifiers::{HeroNameApplier, StartingHeroApplier, MapHeroesRemover}},
+        artifact::{ArtifactInfoProvider, ArtifactPatchesGroup, ArtifactsBans, modifiers::{ArtifactNameApplier, BannedArtifactsReplacer}, table::{ArtifactsTableProvider, ArtifactsTableWriter, BaseArtifact}},
+        objects::CommonObjectsCreator, mechanics::{MoonCalendarWriter, bans::{BansPreset, BaseSpell, MechanicsBans, MechanicsBansGenerator, MechanicsBansProvider}, moon_calendar::{MoonCalendarInfoProvider, MoonCalendarSpec}},
+        info::PatchInfoGenerator,
+        script::{ScriptModulesProvider, MapScriptAssembler}
+    }, 
+    report::{PatchReport, PatchReportWriter},
+    CodeGenerator, FileWriter, TextProcessor
 };
 use serde::{Serialize, Deserialize};
 use tokio::{sync::Mutex, io::AsyncWriteExt};
 use zip::write::FileOptions;
-use std::{path::PathBuf, collections::HashMap, f64::consts::E, io::Read, cell::{RefCell, RefMut}, sync::RwLock};
+use std::{path::PathBuf, collections::HashMap, f64::consts::E, io::Read, cell::{RefCell, RefMut}, sync::RwLock, str::FromStr};
 use std::ops::Range;
 use std::io::Write;
 
-use crate::{file_management::PathManager, SingleValuePayload};
+use crate::{file_management::PathManager, text::LocalizationManager, SingleValuePayload};
 
 /// This module presents functions for all steps of patching process.
 /// The common flow is:
@@ -46,6 +63,8 @@ pub struct PatcherManager {
     pub map: Mutex<Option<Map>>,
     /// Information of possible templates
     pub templates_model: Mutex<TemplatesInfoModel>,
+    /// Definitions of all modes found in config
+    pub mode_definitions: ModeDefinitionsProvider,
     /// Path of configuration files of pactcher
     pub config_path: PathBuf
 }
@@ -58,9 +77,11 @@ impl PatcherManager {
         templates_file.read_to_string(&mut templates_string).unwrap();
         let templates: TemplatesInfoModel = serde_json::from_str(&templates_string).unwrap();
         println!("Templates: {:?}", &templates);
+        let mode_definitions = ModeDefinitionsProvider::new(&patcher_config_path);
         PatcherManager { 
             map: Mutex::new(None), 
             templates_model: Mutex::new(templates), 
+            mode_definitions: mode_definitions,
             config_path: patcher_config_path 
         }
     }
@@ -138,10 +159,375 @@ pub async fn update_player_team_info(
 ) -> Result<(), ()> {
     let mut map_holder = patcher_manager.map.lock().await;
     println!("Updating player's {} team info {} from frontend", &player, &team);
-    map_holder.as_mut().unwrap().teams_info[player] = team;
+    let map = map_holder.as_mut().unwrap();
+    if team != 0 && map.players_control.get(&player).is_some_and(|c| c.control == PlayerControl::Off) {
+        println!("Player {} is turned off and can't be in team", player);
+        return Err(());
+    }
+    map.teams_info[player] = team;
+    Ok(())
+}
+
+/// Invoked when user changes strength scaling of neutral stacks.
+#[tauri::command]
+pub async fn update_stacks_scaling(
+    patcher_manager: State<'_, PatcherManager>,
+    scaling: StacksScaling
+) -> Result<(), ()> {
+    let mut map_holder = patcher_manager.map.lock().await;
+    println!("Updating stacks scaling {:?} from frontend", &scaling);
+    map_holder.as_mut().unwrap().stacks_scaling = scaling;
+    Ok(())
+}
+
+/// Invoked when user changes treasures rebalancing rules.
+#[tauri::command]
+pub async fn update_treasure_rules(
+    patcher_manager: State<'_, PatcherManager>,
+    rules: Vec<TreasureRule>
+) -> Result<(), ()> {
+    let mut map_holder = patcher_manager.map.lock().await;
+    println!("Updating treasure rules {:?} from frontend", &rules);
+    map_holder.as_mut().unwrap().treasure_rules = rules;
+    Ok(())
+}
+
+/// Invoked when user changes the way broken portals are handled.
+#[tauri::command]
+pub async fn set_portals_repair_policy(
+    patcher_manager: State<'_, PatcherManager>,
+    policy: PortalsRepairPolicy
+) -> Result<(), ()> {
+    let mut map_holder = patcher_manager.map.lock().await;
+    println!("Updating portals repair policy {:?} from frontend", &policy);
+    map_holder.as_mut().unwrap().portals_repair_policy = policy;
+    Ok(())
+}
+
+/// Invoked when user changes moon calendar settings, None returns default calendar.
+#[tauri::command]
+pub async fn update_moon_calendar(
+    patcher_manager: State<'_, PatcherManager>,
+    spec: Option<MoonCalendarSpec>
+) -> Result<(), ()> {
+    let mut map_holder = patcher_manager.map.lock().await;
+    println!("Updating moon calendar {:?} from frontend", &spec);
+    map_holder.as_mut().unwrap().moon_calendar = spec;
+    Ok(())
+}
+
+/// Invoked when frontend needs weeks calendar of current settings will have.
+#[tauri::command]
+pub async fn preview_moon_calendar(
+    patcher_manager: State<'_, PatcherManager>
+) -> Result<Vec<String>, ()> {
+    let map_holder = patcher_manager.map.lock().await;
+    let map = map_holder.as_ref().unwrap();
+    match &map.moon_calendar {
+        Some(spec) => {
+            let moon_calendar_info_provider = MoonCalendarInfoProvider::new(&patcher_manager.config_path);
+            moon_calendar_info_provider.generate(spec, map).map_err(|e| {
+                println!("Error generating moon calendar preview: {}", e);
+            })
+        },
+        None => Ok(vec![])
+    }
+}
+
+/// Invoked when user sets seed of patch manually(to reproduce some previous patch for example).
+/// Every random choice of patch(heroes, artifacts, lights, weeks) is made with this seed.
+#[tauri::command]
+pub async fn set_patch_seed(
+    patcher_manager: State<'_, PatcherManager>,
+    seed: u32
+) -> Result<(), ()> {
+    let mut map_holder = patcher_manager.map.lock().await;
+    println!("Updating patch seed {} from frontend", seed);
+    map_holder.as_mut().unwrap().seed = seed;
+    Ok(())
+}
+
+/// Invoked when user changes artifacts bans.
+#[tauri::command]
+pub async fn update_artifacts_bans(
+    patcher_manager: State<'_, PatcherManager>,
+    bans: ArtifactsBans
+) -> Result<(), ()> {
+    let mut map_holder = patcher_manager.map.lock().await;
+    println!("Updating artifacts bans {:?} from frontend", &bans);
+    map_holder.as_mut().unwrap().artifacts_bans = bans;
+    Ok(())
+}
+
+/// Returns artifacts of game's table, so user can choose ones to ban or allow.
+#[tauri::command]
+pub async fn get_base_artifacts(
+    patcher_manager: State<'_, PatcherManager>
+) -> Result<Vec<BaseArtifact>, ()> {
+    let artifacts_table_provider = ArtifactsTableProvider::new(&patcher_manager.config_path);
+    Ok(artifacts_table_provider.get_artifacts())
+}
+
+/// Invoked when user changes spells and skills bans.
+#[tauri::command]
+pub async fn update_mechanics_bans(
+    patcher_manager: State<'_, PatcherManager>,
+    bans: MechanicsBans
+) -> Result<(), ()> {
+    let mut map_holder = patcher_manager.map.lock().await;
+    println!("Updating mechanics bans {:?} from frontend", &bans);
+    map_holder.as_mut().unwrap().mechanics_bans = bans;
+    Ok(())
+}
+
+/// Returns spells of game's table, so user can choose ones to ban or modify.
+#[tauri::command]
+pub async fn get_base_spells(
+    patcher_manager: State<'_, PatcherManager>
+) -> Result<Vec<BaseSpell>, ()> {
+    let mechanics_bans_provider = MechanicsBansProvider::new(&patcher_manager.config_path);
+    Ok(mechanics_bans_provider.get_spells().clone())
+}
+
+/// Returns presets of spells and skills bans.
+#[tauri::command]
+pub async fn get_bans_presets(
+    patcher_manager: State<'_, PatcherManager>
+) -> Result<Vec<BansPreset>, ()> {
+    let mechanics_bans_provider = MechanicsBansProvider::new(&patcher_manager.config_path);
+    Ok(mechanics_bans_provider.get_presets().clone())
+}
+
+/// Invoked when user changes owners or production of mines and dwellings.
+#[tauri::command]
+pub async fn update_economy_rules(
+    patcher_manager: State<'_, PatcherManager>,
+    rules: Vec<EconomyRule>
+) -> Result<(), ()> {
+    let mut map_holder = patcher_manager.map.lock().await;
+    println!("Updating economy rules {:?} from frontend", &rules);
+    map_holder.as_mut().unwrap().economy_rules = rules;
+    Ok(())
+}
+
+/// Checks town building schemes and returns all found problems.
+#[tauri::command]
+pub async fn validate_town_schemes(
+    patcher_manager: State<'_, PatcherManager>
+) -> Result<Vec<String>, ()> {
+    let town_info_provider = TownInfoProvider::new(&patcher_manager.config_path);
+    Ok(town_info_provider.validate_schemes())
+}
+
+/// Returns buildings town of given type will have in current map with given modes.
+#[tauri::command]
+pub async fn preview_town_buildings(
+    patcher_manager: State<'_, PatcherManager>,
+    town_type: town::TownType,
+    modes: Vec<TemplateModeName>,
+    owner: PlayerID
+) -> Result<Vec<SchemedTownBuilding>, ()> {
+    let map_holder = patcher_manager.map.lock().await;
+    let town_info_provider = TownInfoProvider::new(&patcher_manager.config_path);
+    Ok(town_info_provider.preview_buildings(map_holder.as_ref().unwrap(), &modes, &town_type, &owner))
+}
+
+/// Invoked when user changes control settings of some player slot.
+#[tauri::command]
+pub async fn update_player_control_info(
+    patcher_manager: State<'_, PatcherManager>,
+    player: usize,
+    info: PlayerControlInfo
+) -> Result<(), ()> {
+    let mut map_holder = patcher_manager.map.lock().await;
+    println!("Updating player's {} control info {:?} from frontend", &player, &info);
+    let map = map_holder.as_mut().unwrap();
+    if info.control == PlayerControl::Off {
+        match PlayerControlInfo::owned_objects_count(map, player) {
+            Ok(0) => {
+                // turned off slot has no team, so no objectives are generated for it
+                map.teams_info[player] = 0;
+            },
+            Ok(count) => {
+                println!("Player {} owns {} towns and heroes and can't be turned off", player, count);
+                return Err(());
+            },
+            Err(e) => {
+                println!("Impossible to check objects of player {}: {}", player, e);
+                return Err(());
+            }
+        }
+    }
+    map.players_control.insert(player, info);
+    Ok(())
+}
+
+/// Invoked when user changes handicap of some player.
+#[tauri::command]
+pub async fn update_player_handicap(
+    patcher_manager: State<'_, PatcherManager>,
+    player: usize,
+    handicap: PlayerHandicap
+) -> Result<(), ()> {
+    let mut map_holder = patcher_manager.map.lock().await;
+    println!("Updating player's {} handicap {:?} from frontend", &player, &handicap);
+    map_holder.as_mut().unwrap().handicaps.insert(player, handicap);
     Ok(())
 }
 
+#[tauri::command]
+pub async fn remove_player_handicap(
+    patcher_manager: State<'_, PatcherManager>,
+    player: usize
+) -> Result<(), ()> {
+    let mut map_holder = patcher_manager.map.lock().await;
+    map_holder.as_mut().unwrap().handicaps.remove(&player);
+    Ok(())
+}
+
+/// Forces race of player's starting town, None returns race placed by generator.
+#[tauri::command]
+pub async fn set_player_race(
+    patcher_manager: State<'_, PatcherManager>,
+    player: usize,
+    race: Option<town::TownType>
+) -> Result<(), ()> {
+    let mut map_holder = patcher_manager.map.lock().await;
+    let start_info = map_holder.as_mut().unwrap().players_start_info.entry(player).or_default();
+    if race.is_some() && start_info.hero.as_ref().is_some_and(|h| h.race != race.unwrap()) {
+        println!("Starting hero of player {} is removed cause it doesn't match new race", &player);
+        start_info.hero = None;
+    }
+    start_info.race = race;
+    println!("Updating player's {} race {:?} from frontend", &player, &race);
+    Ok(())
+}
+
+/// Sets starting hero of player by its script name, None removes it.
+#[tauri::command]
+pub async fn set_player_starting_hero(
+    patcher_manager: State<'_, PatcherManager>,
+    player: usize,
+    hero: Option<String>
+) -> Result<(), ()> {
+    let mut map_holder = patcher_manager.map.lock().await;
+    let start_info = map_holder.as_mut().unwrap().players_start_info.entry(player).or_default();
+    match hero {
+        Some(hero_name) => {
+            let players_info_provider = PlayersInfoProvider::new(&patcher_manager.config_path);
+            match players_info_provider.get_hero_info(&hero_name) {
+                Some((race, xdb)) => {
+                    if start_info.race.is_some_and(|r| r != *race) {
+                        println!("Hero {} doesn't match race of player {}", &hero_name, &player);
+                        return Err(());
+                    }
+                    start_info.hero = Some(StartingHero {
+                        name: hero_name.clone(),
+                        race: *race,
+                        xdb: xdb.clone()
+                    });
+                },
+                None => {
+                    println!("Hero {} can't be found in heroes pools", &hero_name);
+                    return Err(());
+                }
+            }
+        },
+        None => start_info.hero = None
+    }
+    println!("Updating player's {} starting hero from frontend", &player);
+    Ok(())
+}
+
+/// Starts new hero draft with given order of picks and bans.
+/// Errors of draft are returned to frontend, so organisers can see why order or step is rejected.
+#[tauri::command]
+pub async fn start_hero_draft(
+    patcher_manager: State<'_, PatcherManager>,
+    order: Vec<DraftStep>
+) -> Result<HeroDraft, String> {
+    let mut map_holder = patcher_manager.map.lock().await;
+    let map = map_holder.as_mut().unwrap();
+    let players_count = map.detect_tag_info().map(|info| info.players_count).unwrap_or(0);
+    match HeroDraft::new(order, players_count) {
+        Ok(draft) => {
+            map.hero_draft = Some(draft.clone());
+            println!("Hero draft started: {:?}", &draft);
+            Ok(draft)
+        },
+        Err(e) => {
+            println!("Impossible to start hero draft: {}", &e);
+            Err(e)
+        }
+    }
+}
+
+/// Makes current draft step(pick or ban) with given hero. Returns updated draft.
+#[tauri::command]
+pub async fn make_draft_step(
+    patcher_manager: State<'_, PatcherManager>,
+    hero: String
+) -> Result<HeroDraft, String> {
+    let mut map_holder = patcher_manager.map.lock().await;
+    let players_info_provider = PlayersInfoProvider::new(&patcher_manager.config_path);
+    let town_info_provider = TownInfoProvider::new(&patcher_manager.config_path);
+    let map = map_holder.as_mut().unwrap();
+    let races = known_players_races(map, &town_info_provider)
+        .map_err(|e| format!("Impossible to read players races: {}", e))?;
+    match map.hero_draft.as_mut() {
+        Some(draft) => {
+            match draft.make_step(&hero, &players_info_provider, &races) {
+                Ok(()) => Ok(draft.clone()),
+                Err(e) => {
+                    println!("Impossible to make draft step: {}", &e);
+                    Err(e)
+                }
+            }
+        },
+        None => {
+            println!("Hero draft isn't started");
+            Err("Hero draft isn't started".to_string())
+        }
+    }
+}
+
+/// Returns heroes of every race that are still can be picked or banned in draft.
+#[tauri::command]
+pub async fn get_draft_pools(
+    patcher_manager: State<'_, PatcherManager>
+) -> Result<HashMap<town::TownType, Vec<String>>, ()> {
+    let map_holder = patcher_manager.map.lock().await;
+    let players_info_provider = PlayersInfoProvider::new(&patcher_manager.config_path);
+    match map_holder.as_ref().unwrap().hero_draft.as_ref() {
+        Some(draft) => Ok(draft.pools(&players_info_provider)),
+        None => Ok(HeroDraft { order: vec![], log: vec![] }.pools(&players_info_provider))
+    }
+}
+
+#[tauri::command]
+pub async fn cancel_hero_draft(
+    patcher_manager: State<'_, PatcherManager>
+) -> Result<(), ()> {
+    let mut map_holder = patcher_manager.map.lock().await;
+    map_holder.as_mut().unwrap().hero_draft = None;
+    Ok(())
+}
+
+/// Invoked when user changes lights selection. Returns name of light map will have with it.
+#[tauri::command]
+pub async fn update_lights_selection(
+    patcher_manager: State<'_, PatcherManager>,
+    selection: LightsSelection
+) -> Result<Option<String>, ()> {
+    let mut map_holder = patcher_manager.map.lock().await;
+    println!("Updating lights selection {:?} from frontend", &selection);
+    let map = map_holder.as_mut().unwrap();
+    map.lights_selection = selection;
+    let town_info_provider = TownInfoProvider::new(&patcher_manager.config_path);
+    let light_info_provider = LightsInfoProvider::new(&patcher_manager.config_path, map, &town_info_provider);
+    Ok(light_info_provider.current_light_name().cloned())
+}
+
 /// Invoked when user checks use_night_lights setting.
 #[tauri::command]
 pub async fn set_night_lights_setting(
@@ -249,6 +635,56 @@ pub async fn add_economic_mode(
     Ok(())
 }
 
+/// Returns definitions of all modes, frontend builds settings of custom modes from their params schemas.
+#[tauri::command]
+pub async fn get_mode_definitions(
+    patcher_manager: State<'_, PatcherManager>
+) -> Result<Vec<ModeDefinition>, ()> {
+    Ok(patcher_manager.mode_definitions.definitions().into_iter().cloned().collect())
+}
+
+/// Adds mode known only by its config definition. Params are checked against definition's schema.
+#[tauri::command]
+pub async fn add_custom_mode(
+    patcher_manager: State<'_, PatcherManager>,
+    name: String,
+    params: ModeParams
+) -> Result<(), ()> {
+    // built-in modes have their own settings, definition with their name only describes them
+    if TemplateModeName::from_str(&name).is_ok() {
+        println!("Mode {} is built-in, it can't be added as custom one", &name);
+        return Err(());
+    }
+    let definition = patcher_manager.mode_definitions.get(&name);
+    if definition.is_none() {
+        println!("No definition found for mode {}", &name);
+        return Err(());
+    }
+    match definition.unwrap().validate(&params) {
+        Ok(validated_params) => {
+            let mut map_holder = patcher_manager.map.lock().await;
+            map_holder.as_mut().unwrap().add_custom_mode(name, validated_params);
+            println!("Curr custom modes: {:?}", &map_holder.as_ref().unwrap().custom_modes);
+            Ok(())
+        },
+        Err(e) => {
+            println!("{}", e);
+            Err(())
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn remove_custom_mode(
+    patcher_manager: State<'_, PatcherManager>,
+    name: String
+) -> Result<(), ()> {
+    let mut map_holder = patcher_manager.map.lock().await;
+    map_holder.as_mut().unwrap().remove_custom_mode(&name);
+    println!("Curr custom modes: {:?}", &map_holder.as_ref().unwrap().custom_modes);
+    Ok(())
+}
+
 /// Invoked when user activates patch process.
 /// Creates all necessary patches and runs it.
 /// Repacks map after it.
@@ -256,35 +692,37 @@ pub async fn add_economic_mode(
 pub async fn patch_map(
     app: AppHandle, 
     patcher_manager: State<'_, PatcherManager>,
-    path_manager: State<'_, PathManager>
+    path_manager: State<'_, PathManager>,
+    localization_manager: State<'_, LocalizationManager>
 ) -> Result<(), ()> {
     let map_locked = patcher_manager.map.lock().await;
+    let localization = localization_manager.service.lock().await;
     let mut map = map_locked.as_ref().unwrap();
 
     let config = patcher_manager.config_path.clone();
     let config_common_dir = config.join("adds\\common\\");
-    let map_modes:Vec<_> = map.modes.clone().into_keys().collect();
+    let enabled_strategies = patcher_manager.mode_definitions.enabled_strategies(map);
+    let patch_report = RwLock::new(PatchReport::new());
+    let substitutions_provider = SubstitutionsProvider::new(&config);
+    let active_substitutions = substitutions_provider.get_active(map);
     // Town patches group
     let town_info_provider = TownInfoProvider::new(&config);
     let mut player_race_cross_patch_info = RwLock::new(PlayerRaceCrossPatchInfo::new());
     let mut neutral_town_cross_patch_info = NeutralTownCrossPatchInfo{neutral_town_name: None};
-    let mut town_name_applier = TownNameApplier::new(map.modes.contains_key(&TemplateModeName::CaptureObject));
-    let mut default_town_scheme_applier = DefaultTownSchemesApplier::new(&town_info_provider, &map_modes);
-    let mut neutral_dwellings_disabler = NeutralTownDwellingsDisabler::new(
-        map.settings.disable_neutral_towns_dwells, 
-        &town_info_provider
-    );
+    let mut town_name_applier = TownNameApplier::new(enabled_strategies.contains(&ModeStrategy::CapturableTown));
+    let mut player_race_applier = PlayerRaceApplier::new(&town_info_provider, map);
+    let mut default_town_scheme_applier = DefaultTownSchemesApplier::new(&town_info_provider, map);
     let mut town_active_tile_detector = TownActiveTilesDetector::new(&config, &town_info_provider);
     let mut player_race_detector = PlayerRaceDetector::new(&player_race_cross_patch_info, &town_info_provider);
     let mut capturable_town_detector = CapturableTownDetector::new(
         &town_info_provider, 
         &mut neutral_town_cross_patch_info, 
-        map.modes.contains_key(&TemplateModeName::CaptureObject)
+        enabled_strategies.contains(&ModeStrategy::CapturableTown)
     );
     let mut town_patch_group = TownPatchesGroup::new()
         .with_modifyable(&mut town_name_applier)
+        .with_modifyable(&mut player_race_applier)
         .with_modifyable(&mut default_town_scheme_applier)
-        .with_modifyable(&mut neutral_dwellings_disabler)
         .with_getter(&mut town_active_tile_detector)
         .with_getter(&mut player_race_detector)
         .with_getter(&mut capturable_town_detector);
@@ -296,46 +734,125 @@ pub async fn patch_map(
         &mut player_info_provider, 
         &player_race_cross_patch_info, 
         &player_cross_patch_info, 
-        map.modes.contains_key(&TemplateModeName::Outcast)
+        enabled_strategies.contains(&ModeStrategy::OutcastHeroes) && map.hero_draft.is_none(),
+        map.seed
+    );
+    let mut draft_hero_selector = DraftHeroSelector::new(
+        map.hero_draft.as_ref(),
+        &player_race_cross_patch_info, 
+        &player_cross_patch_info
     );
+    let mut starting_hero_selector = StartingHeroSelector::new(
+        &map.players_start_info,
+        &player_race_cross_patch_info, 
+        &player_cross_patch_info
+    );
+    let mut player_handicap_applier = PlayerHandicapApplier::new(&map.handicaps, &player_cross_patch_info);
+    let mut player_control_applier = PlayerControlApplier::new(&map.players_control, &player_cross_patch_info);
     let mut inactive_player_tavern_filter_remover = InactivePlayersTavernFilterRemover{};
+    // control applier goes first, so players turned off get no heroes, patches count slots, so their numbers don't shift
     let mut player_patch_group = PlayerPatchesGroup::new()
+        .with_modifyable(&mut player_control_applier)
         .with_modifyable(&mut player_team_selector)
         .with_modifyable(&mut outcast_player_hero_selector)
+        .with_modifyable(&mut draft_hero_selector)
+        .with_modifyable(&mut starting_hero_selector)
+        .with_modifyable(&mut player_handicap_applier)
         .with_modifyable(&mut inactive_player_tavern_filter_remover);
     // Treasure patches group
     let treasure_info_provider = TreasureInfoProvider::new(&config);
     let mut treasure_name_applier = TreasureNameApplier::new();
+    let mut treasure_substitutor = ObjectSubstitutor::<AdvMapTreasure>::new(active_substitutions.clone(), &patch_report);
+    let mut treasure_rebalancer = TreasureRebalancer::new(&map.treasure_rules, &treasure_info_provider, &patch_report);
     let mut treasure_props_detector = TreasurePropsDetector::new(&treasure_info_provider);
     let mut treasure_patch_group = TreasurePatchesGroup::new()
         .with_modifyable(&mut treasure_name_applier)
+        .with_modifyable(&mut treasure_substitutor)
+        .with_modifyable(&mut treasure_rebalancer)
         .with_getter(&mut treasure_props_detector);
+    // Artifact patches group
+    let artifact_info_provider = ArtifactInfoProvider::new(&config);
+    let mut artifact_name_applier = ArtifactNameApplier::new();
+    let mut banned_artifacts_replacer = BannedArtifactsReplacer::new(
+        &artifact_info_provider, 
+        &map.artifacts_bans, 
+        map.settings.enable_new_arts, 
+        map.seed,
+        &patch_report
+    );
+    let mut artifact_patch_group = ArtifactPatchesGroup::new(&artifact_info_provider)
+        .with_modifyable(&mut artifact_name_applier)
+        .with_modifyable(&mut banned_artifacts_replacer);
+    // Hero patches group
+    let hero_info_provider = HeroInfoProvider::new(&config);
+    let mut hero_name_applier = HeroNameApplier::new();
+    let mut starting_hero_applier = StartingHeroApplier::new(&map.players_start_info);
+    let mut map_heroes_remover = MapHeroesRemover::new(enabled_strategies.contains(&ModeStrategy::NoMapHeroes));
+    let mut hero_substitutor = ObjectSubstitutor::<AdvMapHero>::new(active_substitutions.clone(), &patch_report);
+    let mut hero_patch_group = HeroPatchesGroup::new(&hero_info_provider)
+        .with_modifyable(&mut hero_name_applier)
+        .with_modifyable(&mut starting_hero_applier)
+        .with_modifyable(&mut map_heroes_remover)
+        .with_modifyable(&mut hero_substitutor);
+    // Mines and dwellings patches groups
+    let economy_info_provider = EconomyInfoProvider::new(&config);
+    let mut mine_name_applier = MineNameApplier::new();
+    let mut mine_owner_applier = MineOwnerApplier::new(&economy_info_provider, &map.economy_rules);
+    let mut mine_patch_group = MinePatchesGroup::new(&economy_info_provider, &map.economy_rules)
+        .with_modifyable(&mut mine_name_applier)
+        .with_modifyable(&mut mine_owner_applier);
+    let mut dwelling_name_applier = DwellingNameApplier::new();
+    let mut dwelling_owner_applier = DwellingOwnerApplier::new(&economy_info_provider, &map.economy_rules);
+    let mut dwelling_patch_group = DwellingPatchesGroup::new(&economy_info_provider, &map.economy_rules)
+        .with_modifyable(&mut dwelling_name_applier)
+        .with_modifyable(&mut dwelling_owner_applier);
     // Building patches group
     let building_info_provider = BuildingInfoProvider::new(&config);
     let mut building_name_applier = BuildingNameApplier::new();
-    let mut outcast_tavern_replacer = OutcastTavernReplacer::new(map.modes.contains_key(&TemplateModeName::Outcast));
+    let mut building_substitutor = ObjectSubstitutor::<AdvMapBuilding>::new(active_substitutions.clone(), &patch_report);
+    let portals_analysis = PortalsAnalysis::new(map, map.portals_repair_policy, &patch_report);
+    let mut portals_repairer = PortalsRepairer::new(&portals_analysis);
     let mut building_type_detector = BuildingTypeDetector::new(&building_info_provider);
+    // portals are repaired before substitutions, cause substitution can turn portal into other building.
     let mut building_patch_group = BuildingPatchesGroup::new()
         .with_modifyable(&mut building_name_applier)
-        .with_modifyable(&mut outcast_tavern_replacer)
+        .with_modifyable(&mut portals_repairer)
+        .with_modifyable(&mut building_substitutor)
         .with_getter(&mut building_type_detector);
     // Creature patches group
     let mut creature_name_applier = CreatureNameApplier::new();
     let mut additional_stack_fixer = AdditionalStackFixer{};
+    let mut creature_substitutor = ObjectSubstitutor::<AdvMapMonster>::new(active_substitutions.clone(), &patch_report);
+    let creature_info_provider = CreatureInfoProvider::new(&config);
+    // banks are only needed for guards scaling, so map is read only if such rule exists.
+    let banks_positions = match map.stacks_scaling.rules.iter().any(|r| matches!(r, StackScalingRule::BankGuards {..})) {
+        true => building_info_provider.get_banks_positions(map),
+        false => vec![]
+    };
+    let mut neutral_stack_scaler = NeutralStackScaler::new(
+        &map.stacks_scaling, 
+        &creature_info_provider, 
+        banks_positions, 
+        &patch_report
+    );
     let mut creature_patch_group = CreaturePatchesGroup::new()
         .with_modifyable(&mut creature_name_applier)
-        .with_modifyable(&mut additional_stack_fixer);
+        .with_modifyable(&mut creature_substitutor)
+        .with_modifyable(&mut additional_stack_fixer)
+        .with_modifyable(&mut neutral_stack_scaler);
     // Quest patches group
     let quest_info_provider = QuestInfoProvider::new(&config);
     let mut map_init_quest_creator = MapInitQuestCreator::new(&quest_info_provider);
-    let mut map_modes_quest_creator = MapModesQuestCreator::new(&quest_info_provider);
+    let objectives_provider = ObjectivesProvider::new(&config);
+    let generated_objectives = objectives_provider.generate(map, &patcher_manager.mode_definitions);
+    let mut modes_objectives_creator = ModesObjectivesCreator::new(&generated_objectives);
     let mut empty_items_fixer = QuestEmptyItemsFixer{};
     let mut quest_patch_group = QuestPatchesGroup::new()
         .with_modifyable(&mut map_init_quest_creator)
-        .with_modifyable(&mut map_modes_quest_creator)
+        .with_modifyable(&mut modes_objectives_creator)
         .with_modifyable(&mut empty_items_fixer);
     // Lights patches
-    let light_info_provider = LightsInfoProvider::new(&config, map.settings.use_night_lights);
+    let light_info_provider = LightsInfoProvider::new(&config, map, &town_info_provider);
     let ambient_light_creator = AmbientLightCreator::new(&light_info_provider);
     let ground_ambient_lights_creator = GroundAmbientLightsCreator::new(&light_info_provider);
     //
@@ -344,12 +861,12 @@ pub async fn patch_map(
         &map.main_dir
     );
     //
-    let final_battle_active = map.modes.contains_key(&TemplateModeName::FinalBattle);
+    let final_battle_active = enabled_strategies.contains(&ModeStrategy::UndergroundArena);
     let common_objects_creator = CommonObjectsCreator::new(&config, final_battle_active);
     let underground_enabler = UndergroundEnabler::new(final_battle_active);
     let underground_name_applier = UndergroundTerrainNameApplier::new(final_battle_active);
     let available_heroes_writer = AvailableHeroesWriter::new(
-        map.modes.contains_key(&TemplateModeName::Outcast), 
+        enabled_strategies.contains(&ModeStrategy::OutcastHeroes) || map.hero_draft.is_some(), 
         &player_cross_patch_info
     );
     let map_xdb_patcher = Patcher::new()
@@ -357,6 +874,10 @@ pub async fn patch_map(
         .with_modifyables("AdvMapTown", &mut town_patch_group)
         .with_modifyables("players", &mut player_patch_group)
         .with_modifyables("AdvMapTreasure", &mut treasure_patch_group)
+        .with_modifyables("AdvMapArtifact", &mut artifact_patch_group)
+        .with_modifyables("AdvMapMine", &mut mine_patch_group)
+        .with_modifyables("AdvMapHero", &mut hero_patch_group)
+        .with_modifyables("AdvMapDwelling", &mut dwelling_patch_group)
         .with_modifyables("AdvMapBuilding", &mut building_patch_group)
         .with_modifyables("AdvMapMonster", &mut creature_patch_group)
         .with_modifyables("Objectives", &mut quest_patch_group)
@@ -385,65 +906,92 @@ pub async fn patch_map(
     );
     let modes_path = config.join("adds\\win_conditions\\");
     let map_modes_info_generator = ModesInfoGenerator::new(
-        &map.modes, 
+        map, 
+        &patcher_manager.mode_definitions,
         &modes_path, 
-        &map.main_dir
+        &map.main_dir,
+        &localization
     );
+    // // ------- CODE GENERATORS -------
+    let players_info_generator = PlayersInfoGenerator::new(&player_cross_patch_info);
+    let patch_info_generator = PatchInfoGenerator::new(map, env!("CARGO_PKG_VERSION"));
+    let mechanics_bans_provider = MechanicsBansProvider::new(&config);
+    let mechanics_bans_generator = MechanicsBansGenerator::new(&mechanics_bans_provider, map, &patch_report);
+    let mut code_generator = CodeGenerator::new();
+    code_generator
+        .with(&building_patch_group)
+        .with(&treasure_patch_group)
+        .with(&artifact_patch_group)
+        .with(&mine_patch_group)
+        .with(&hero_patch_group)
+        .with(&dwelling_patch_group)
+        .with(&map_modes_info_generator)
+        .with(&creature_patch_group)
+        .with(&town_patch_group)
+        .with(&players_info_generator)
+        .with(&patch_info_generator)
+        .with(&mechanics_bans_generator)
+        .run(&map.main_dir);
+    // map script needs to know generated tables to include modules handling them
+    let script_modules_provider = ScriptModulesProvider::new(&config);
+    let script_assembly = script_modules_provider.assemble(map, &code_generator.generated_tables());
+    let moon_calendar_info_provider = MoonCalendarInfoProvider::new(&config);
+    let generated_moon_calendar = map.moon_calendar.as_ref()
+        .and_then(|spec| moon_calendar_info_provider.create(spec, map, &patch_report));
+    let artifacts_table_provider = ArtifactsTableProvider::new(&config);
+    let artifacts_table = artifacts_table_provider.generate(map, &artifact_info_provider, &patch_report);
     let file_writer = FileWriter::new()
         .with(&MoonCalendarWriter::new(
             map.settings.only_neutral_weeks,
+            generated_moon_calendar,
             &map.game_mechanics_dir,
             &patcher_manager.config_path.join("adds\\moon_calendar\\Default.xdb")
         ))
-        .with(&NewArtifactsEnabler::new(
-            map.settings.enable_new_arts, 
-            &map.game_mechanics_dir, 
-            &config_common_dir.join("Artifacts.xdb")))
+        .with(&ArtifactsTableWriter::new(artifacts_table, &map.game_mechanics_dir))
         .with(&OutcastMechanicsWriter::new(
-            map.modes.contains_key(&TemplateModeName::Outcast),
+            enabled_strategies.contains(&ModeStrategy::OutcastMechanics),
             &map.game_mechanics_dir,
             vec![
                 (&patcher_manager.config_path.join("adds\\outcast\\Summon_Creatures.xdb"), &PathBuf::from("Spell\\Adventure_Spells\\Summon_Creatures.xdb")),
                 (&patcher_manager.config_path.join("adds\\outcast\\Summon_Boat.xdb"), &PathBuf::from("Spell\\Adventure_Spells\\Summon_Boat.xdb"))
             ]
         ))
+        // goes after outcast mechanics, cause its spells can be changed by bans
+        .with(&mechanics_bans_generator)
         .with(&OutcastTextWriter::new(
-            map.modes.contains_key(&TemplateModeName::Outcast),
+            enabled_strategies.contains(&ModeStrategy::OutcastMechanics),
             &map.text_dir,
             &patcher_manager.config_path.join("adds\\outcast\\Long_Description.txt")
         ))
         .with(&underground_terrain_creator)
         .with(&map_modes_info_generator)
+        .with(&DraftLogWriter::new(map.hero_draft.as_ref(), &map.main_dir))
+        .with(&MapScriptAssembler::new(&script_assembly, &patch_report, &map.main_dir))
         .run();
-    // // ------- CODE GENERATORS -------
-    let code_generator = CodeGenerator::new()
-        .with(&building_patch_group)
-        .with(&treasure_patch_group)
-        .with(&map_modes_info_generator)
-        .with(&creature_patch_group)
-        .with(&town_patch_group)
-        .run(&map.main_dir);
-
     // // ------ TEXT PROCESSORS ------
     let base_text_processor = TextProcessor::new(&map.map_name)
         .with(&MapNameChanger{})
         .run();
-    // win condition quests processing
-    let fbtp = TextProcessor::new(&map.main_dir.join("final_battle_desc.txt"))
-        .with(&FinalBattleModeTextProcessor {
-            final_battle_time: map.get_mode(&TemplateModeName::FinalBattle)
-        })
-        .run();
-    let etp = TextProcessor::new(&map.main_dir.join("economic_desc.txt"))
-        .with(&EconomicModeTextProcessor {
-            resource_info: map.get_mode(&TemplateModeName::Economic)
-        })
-        .run();
-    let cotp = TextProcessor::new(&map.main_dir.join("capture_object_desc.txt"))
-        .with(&CaptureObjectModeTextProcessor::new(
-            map.get_mode(&TemplateModeName::CaptureObject),
-            &neutral_town_cross_patch_info
-        ))
+    // win condition quests processing, descriptions of active modes(built-in and custom ones) are rendered with their params
+    let active_modes: Vec<(String, ModeParams)> = map.modes.iter()
+        .map(|mode| (mode.0.to_string(), mode.1.to_params()))
+        .chain(map.custom_modes.iter().map(|mode| (mode.0.clone(), mode.1.clone())))
+        .collect();
+    for (mode, params) in active_modes.iter() {
+        let definition = match patcher_manager.mode_definitions.get(mode) {
+            Some(definition) => definition,
+            None => continue
+        };
+        if let Some(quest_files) = definition.quest_files.as_ref() {
+            let mode_text_context = TemplateContext::from_params(definition, params, &neutral_town_cross_patch_info);
+            TextProcessor::new(&map.main_dir.join(&quest_files.desc))
+                .with(&ModeTextProcessor::new(&localization, &mode_text_context, &quest_files.desc, &patch_report))
+                .run();
+        }
+    }
+    // text processors record errors too, so report is written after them
+    FileWriter::new()
+        .with(&PatchReportWriter::new(&patch_report, &map.main_dir))
         .run();
     zip_map(&map.name, &map.dir);
     // move base map
@@ -1,6 +1,6 @@
 use crate::file_management::PathManager;
 use tauri::State;
-use std::{io::Write, collections::HashMap};
+use std::{io::{Write, Read}, collections::HashMap, path::PathBuf};
 use homm5_scaner::{
     pak::FileStructure,
     entity::{
@@ -11,9 +11,49 @@ use homm5_scaner::{
         spell::{SpellFileCollector, SpellScaner}
     }
 };
+use patcher::patch_strategy::{artifact::table::read_artifacts, mechanics::bans::read_spells};
 
 /// This mod is used for lua code generation for game files entities like creatures, heroes etc.
 
+/// Returns names and contents of newest versions of files among given paks(game uses the last modified ones).
+/// Files are selected by their lowercase names.
+fn extract_newest_files(data_dir: &PathBuf, paks: &Vec<String>, is_needed: impl Fn(&str) -> bool) -> HashMap<String, (String, String)> {
+    let mut newest: HashMap<String, (u32, String, String)> = HashMap::new();
+    for pak in paks.iter().filter(|p| p.ends_with(".pak")) {
+        let pak_file = match std::fs::File::open(data_dir.join(pak)) {
+            Ok(file) => file,
+            Err(_e) => continue
+        };
+        let mut archive = match zip::ZipArchive::new(pak_file) {
+            Ok(archive) => archive,
+            Err(e) => {
+                println!("Error opening pak {}: {}", pak, e.to_string());
+                continue;
+            }
+        };
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).unwrap();
+            let name = entry.name().to_lowercase();
+            if is_needed(&name) == false {
+                continue;
+            }
+            let modified = entry.last_modified();
+            let time = ((modified.datepart() as u32) << 16) | modified.timepart() as u32;
+            if newest.get(&name).is_some_and(|n| n.0 >= time) {
+                continue;
+            }
+            let mut content = String::new();
+            match entry.read_to_string(&mut content) {
+                Ok(_) => {
+                    newest.insert(name, (time, entry.name().to_string(), content));
+                },
+                Err(e) => println!("Error reading {} from pak {}: {}", entry.name(), pak, e.to_string())
+            }
+        }
+    }
+    newest.into_iter().map(|f| (f.0, (f.1.1, f.1.2))).collect()
+}
+
 /// Checks entities in .pak files of game's data folder, reads their contents, scans them and create new pak with generated scripts.
 #[tauri::command]
 pub async fn scan_files(path_manager: State<'_, PathManager>) -> Result<(),()> {
@@ -80,5 +120,33 @@ pub async fn scan_files(path_manager: State<'_, PathManager>) -> Result<(),()> {
     map_zipped.start_file("scripts/generated/spells.lua", Default::default()).unwrap();
     map_zipped.write_all(spells_generated_file.as_bytes()).unwrap();
     map_zipped.finish().unwrap();
+    // game's tables and spells are used by patcher as base of maps' GameMechanics changes
+    let game_files = extract_newest_files(path_manager.data(), &paks, |name| {
+        name == "gamemechanics/reftables/artifacts.xdb" ||
+        name == "gamemechanics/reftables/undividedspells.xdb" ||
+        name == "gamemechanics/reftables/heroclass.xdb" ||
+        (name.starts_with("gamemechanics/spell/") && name.ends_with(".xdb"))
+    });
+    if game_files.contains_key("gamemechanics/reftables/artifacts.xdb") == false {
+        println!("Artifacts table isn't found in game files");
+    }
+    let game_files_dir = path_manager.cfg().join("patcher\\adds\\game\\");
+    for (name, content) in game_files.values() {
+        let path_to = game_files_dir.join(name.replace("/", "\\"));
+        std::fs::create_dir_all(path_to.parent().unwrap()).unwrap();
+        let mut file = std::fs::File::create(&path_to).unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+    }
+    // artifacts and spells of game's tables are stored too, so patcher doesn't search for them in tables every time
+    if let Some((_, table)) = game_files.get("gamemechanics/reftables/artifacts.xdb") {
+        let artifacts = read_artifacts(table);
+        let mut file = std::fs::File::create(game_files_dir.join("artifacts.json")).unwrap();
+        file.write_all(serde_json::to_string_pretty(&artifacts).unwrap().as_bytes()).unwrap();
+    }
+    if let Some((_, table)) = game_files.get("gamemechanics/reftables/undividedspells.xdb") {
+        let spells = read_spells(table);
+        let mut file = std::fs::File::create(game_files_dir.join("spells.json")).unwrap();
+        file.write_all(serde_json::to_string_pretty(&spells).unwrap().as_bytes()).unwrap();
+    }
     Ok(())
 }
\ No newline at end of file
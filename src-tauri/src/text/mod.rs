@@ -2,13 +2,54 @@ use std::{path::PathBuf, collections::HashMap, sync::Mutex, str::FromStr, fs::Fi
 use serde::{Serialize, Deserialize, Serializer};
 use serde_json::Value;
 use tauri::{State, Manager};
-use strum_macros::EnumString;
 
-#[derive(Default, Deserialize, Serialize, PartialEq, Eq, Hash, Debug, EnumString)]
-pub enum Locale {
-    #[default]
-    Ru,
-    En
+pub use patcher::locale::Locale;
+use patcher::locale::LocalizationService;
+use crate::{file_management::PathManager, game_mode::BaseSettings};
+
+/// Holds strings of active locale for launcher and patcher.
+pub struct LocalizationManager {
+    pub service: tokio::sync::Mutex<LocalizationService>
+}
+
+impl LocalizationManager {
+    pub fn new(path_manager: &PathManager) -> Self {
+        let settings = BaseSettings::read(path_manager);
+        LocalizationManager {
+            service: tokio::sync::Mutex::new(LocalizationService::new(&path_manager.cfg().join("locale\\"), settings.locale))
+        }
+    }
+}
+
+/// Invoked when user changes locale, it is saved into settings.
+#[tauri::command]
+pub async fn set_locale(
+    localization_manager: State<'_, LocalizationManager>,
+    path_manager: State<'_, PathManager>,
+    locale: Locale
+) -> Result<(), ()> {
+    println!("Updating locale {:?} from frontend", &locale);
+    let mut settings = BaseSettings::read(&path_manager);
+    settings.locale = locale;
+    settings.write(&path_manager);
+    localization_manager.service.lock().await.set_locale(locale);
+    Ok(())
+}
+
+/// Returns strings of active locale for frontend.
+#[tauri::command]
+pub async fn get_locale_strings(
+    localization_manager: State<'_, LocalizationManager>
+) -> Result<HashMap<String, String>, ()> {
+    Ok(localization_manager.service.lock().await.strings())
+}
+
+/// Returns keys of strings every locale lacks.
+#[tauri::command]
+pub async fn get_missing_locale_keys(
+    localization_manager: State<'_, LocalizationManager>
+) -> Result<HashMap<Locale, Vec<String>>, ()> {
+    Ok(localization_manager.service.lock().await.missing_keys())
 }
 
 #[derive(Default, Deserialize, Serialize, Debug)]
@@ -12,7 +12,7 @@ use oauth2::{hyper::client::HttpConnector, service_account_impersonator};
 use crate::drive;
 use crate::file_management::{FileLoadInfo, FileMoveType, FileLoadType};
 use crate::database::{DatabaseManager, WriteDBItem};
-use crate::{file_management::PathManager, drive::DriveManager, game_mode::{GameModeManager, GameMode}, SingleValuePayload};
+use crate::{file_management::PathManager, drive::DriveManager, game_mode::{GameModeManager, GameMode}, text::LocalizationManager, SingleValuePayload};
 
 /// This module contains functions for steps of update process.
 
@@ -355,8 +355,10 @@ async fn download_file(
 
 /// Updates file name on frontend
 async fn set_updated_file_name(app: &AppHandle, name: &String) {
+    let localization_manager = app.state::<LocalizationManager>();
+    let value = localization_manager.service.lock().await.format("update.loading_file", &[("file", name)]);
     app.emit_to("main", "updated_file_changed", SingleValuePayload {
-        value: format!("Загружается {}", name)
+        value: value
     });
 }
 